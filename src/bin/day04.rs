@@ -66,7 +66,7 @@ fn test_get_number_of_wins() {
 fn parse_numbers(line: &str) -> Vec<usize> {
     line
         .split(' ')
-        .filter(|x| *x != "")
+        .filter(|x| !x.is_empty())
         .map(|x| x.parse().unwrap())
         .collect()
 }
@@ -77,17 +77,79 @@ fn test_parse_numbers() {
     assert_eq!([83, 86, 6, 31, 17, 9, 48, 53], parse_numbers("83 86  6 31 17  9 48 53")[..]);
 }
 
+#[derive(Debug, PartialEq)]
+struct CardCascade {
+    copies: usize,
+    contributions: Vec<(usize, usize)>,
+}
+
 fn process_input_pt2(input: &str) -> usize {
+    get_card_cascade(input).iter().map(|x| x.copies).sum()
+}
+
+fn get_card_cascade(input: &str) -> Vec<CardCascade> {
     let input = input.trim();
     let lines_count = input.lines().count();
-    let mut copy_counts = vec![1; lines_count];
+    let mut cascade = (0..lines_count)
+        .map(|_| CardCascade{copies: 1, contributions: vec![]})
+        .collect::<Vec<_>>();
     let last_index = lines_count - 1;
     for (index, line) in input.lines().enumerate() {
+        let card_id = parse_card_id(line);
+        if card_id != index + 1 {
+            panic!("unexpected card id {} on line {}, expected {}", card_id, index + 1, index + 1);
+        }
         let number_of_wins = get_number_of_wins(line);
-        let number_of_copies = copy_counts[index];
-        for copy_index in (index + 1)..=(cmp::min(index + number_of_wins, last_index)) {
-            copy_counts[copy_index] += number_of_copies;
+        let number_of_copies = cascade[index].copies;
+        for card in &mut cascade[(index + 1)..=(cmp::min(index + number_of_wins, last_index))] {
+            card.copies += number_of_copies;
+            card.contributions.push((card_id, number_of_copies));
         }
     }
-    copy_counts.iter().sum()
+    cascade
+}
+
+#[test]
+fn test_get_card_cascade() {
+    let input = "
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+    let cascade = get_card_cascade(input);
+    let copies = cascade.iter().map(|x| x.copies).collect::<Vec<_>>();
+    assert_eq!([1, 2, 4, 8, 14, 1], copies[..]);
+    assert!(cascade[0].contributions.is_empty());
+    assert_eq!([(1, 1)], cascade[1].contributions[..]);
+    assert_eq!([(1, 1), (2, 2)], cascade[2].contributions[..]);
+    assert_eq!([(1, 1), (2, 2), (3, 4)], cascade[3].contributions[..]);
+    assert_eq!([(1, 1), (3, 4), (4, 8)], cascade[4].contributions[..]);
+    assert!(cascade[5].contributions.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "unexpected card id 3 on line 2")]
+fn test_get_card_cascade_not_sequential() {
+    let input = "
+Card 1: 41 48 | 83 86
+Card 3: 13 32 | 61 30
+";
+    get_card_cascade(input);
+}
+
+fn parse_card_id(line: &str) -> usize {
+    let (card_title, _) = line.split_once(':').unwrap();
+    let Some(card_id) = card_title.strip_prefix("Card") else {
+        panic!("unexpected card title {}", card_title);
+    };
+    card_id.trim().parse().unwrap()
+}
+
+#[test]
+fn test_parse_card_id() {
+    assert_eq!(1, parse_card_id("Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53"));
+    assert_eq!(123, parse_card_id("Card 123: 41 48 | 83 86"));
 }
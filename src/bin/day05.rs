@@ -1,5 +1,9 @@
-use std::fs::read_to_string;
 use std::cmp;
+use std::collections::HashMap;
+use std::env;
+use std::fs::read_to_string;
+use std::ops::Range;
+use rust_aoc_2023::interval_set::IntervalSet;
 
 fn main() {
     let file_contents = read_to_string("inputs/day05.txt").unwrap();
@@ -7,51 +11,149 @@ fn main() {
     println!("{}", result);
     let result = process_input(&file_contents, true);
     println!("{}", result);
+    if env::args().any(|x| x == "--trace") {
+        print!("{}", trace_min_location(&file_contents));
+    }
 }
 
-fn process_input(input: &str, parse_as_ranges: bool) -> isize {
-    let sections = input.trim().split("\n\n").filter(|&x| x != "");
-    let mut seeds = vec![];
-    let mut seed_to_soil_map = vec![];
-    let mut soil_to_fertilizer_map = vec![];
-    let mut fertilizer_to_water_map = vec![];
-    let mut water_to_light_map = vec![];
-    let mut light_to_temperature_map = vec![];
-    let mut temperature_to_humidity_map = vec![];
-    let mut humidity_to_location_map = vec![];
-    for section in sections {
-        let (section_head, section_body) = section.split_once(':').unwrap();
-        match section_head {
-            "seeds" => seeds = parse_seeds(section_body, parse_as_ranges),
-            _ => {
-                let parsed_map = parse_map(section_body);
-                match section_head {
-                    "seed-to-soil map" => seed_to_soil_map = parsed_map,
-                    "soil-to-fertilizer map" => soil_to_fertilizer_map = parsed_map,
-                    "fertilizer-to-water map" => fertilizer_to_water_map = parsed_map,
-                    "water-to-light map" => water_to_light_map = parsed_map,
-                    "light-to-temperature map" => light_to_temperature_map = parsed_map,
-                    "temperature-to-humidity map" => temperature_to_humidity_map = parsed_map,
-                    "humidity-to-location map" => humidity_to_location_map = parsed_map,
-                    _ => panic!("unknown section {}", section_head),
+type MapLine = (isize, isize, isize);
+
+#[derive(Clone, Debug, PartialEq)]
+struct MappingPiece {
+    range: Range<isize>,
+    offset: isize,
+    lines: Vec<Option<MapLine>>,
+}
+
+#[derive(Debug, PartialEq)]
+struct RangeTrace {
+    steps: Vec<(Range<isize>, Option<MapLine>)>,
+    output: Range<isize>,
+}
+
+#[derive(Debug)]
+struct Almanac {
+    categories: Vec<String>,
+    mapping: Vec<MappingPiece>,
+}
+
+impl Almanac {
+    fn parse<'a>(sections: impl Iterator<Item = &'a str>) -> Self {
+        let mut maps_by_source = HashMap::new();
+        for section in sections {
+            let (section_head, section_body) = section.split_once(':').unwrap();
+            let Some((from, to)) = section_head.strip_suffix(" map").and_then(|x| x.split_once("-to-")) else {
+                panic!("unknown section {}", section_head);
+            };
+            if maps_by_source.insert(from, (to, parse_map(section_body))).is_some() {
+                panic!("duplicate map from {}", from);
+            }
+        }
+
+        let mut starts = maps_by_source
+            .keys()
+            .filter(|&from| maps_by_source.values().all(|(to, _)| to != from));
+        let Some(&start) = starts.next() else {
+            panic!("maps have no starting category");
+        };
+        if let Some(other) = starts.next() {
+            panic!("maps have multiple starting categories {} and {}", start, other);
+        }
+
+        let mut categories = vec![start.to_string()];
+        let mut mapping = vec![MappingPiece{range: isize::MIN..isize::MAX, offset: 0, lines: vec![]}];
+        let mut current = start;
+        while let Some((to, maps)) = maps_by_source.remove(current) {
+            mapping = compose_mapping(&mapping, &get_stage_mapping(&maps));
+            categories.push(to.to_string());
+            current = to;
+        }
+        if !maps_by_source.is_empty() {
+            panic!("maps are not connected to the chain: {:?}", maps_by_source.keys());
+        }
+        Self{categories, mapping}
+    }
+
+    fn forward(&self, value: isize) -> isize {
+        let index = self.mapping.partition_point(|piece| piece.range.end <= value);
+        value + self.mapping[index].offset
+    }
+
+    #[allow(dead_code)]
+    fn inverse(&self, value: isize) -> Vec<isize> {
+        self.mapping
+            .iter()
+            .map(|piece| (&piece.range, value - piece.offset))
+            .filter(|(range, source)| range.contains(source))
+            .map(|(_, source)| source)
+            .collect()
+    }
+
+    fn map_ranges(&self, ranges: &IntervalSet<isize>) -> IntervalSet<isize> {
+        apply_mapping(ranges, &self.mapping)
+    }
+
+    fn trace_ranges(&self, ranges: &IntervalSet<isize>) -> Vec<RangeTrace> {
+        let mut traces = vec![];
+        for piece in &self.mapping {
+            for range in ranges.intersection(&IntervalSet::from(piece.range.clone())).ranges() {
+                let mut steps = vec![];
+                let mut current = range.clone();
+                for &line in &piece.lines {
+                    let offset = line.map_or(0, |(dest, src, _)| dest - src);
+                    let next = (current.start + offset)..(current.end + offset);
+                    steps.push((current, line));
+                    current = next;
                 }
+                traces.push(RangeTrace{steps, output: current});
             }
         }
+        traces.sort_by_key(|trace| trace.output.start);
+        traces
     }
-    let map_stages = [
-        seed_to_soil_map,
-        soil_to_fertilizer_map,
-        fertilizer_to_water_map,
-        water_to_light_map,
-        light_to_temperature_map,
-        temperature_to_humidity_map,
-        humidity_to_location_map,
-    ];
-    let mut mapped_ranges = seeds;
-    for stage in map_stages {
-        mapped_ranges = map_ranges(&mapped_ranges, &stage);
+
+    fn format_trace(&self, trace: &RangeTrace) -> String {
+        let mut result = String::new();
+        for (index, (range, line)) in trace.steps.iter().enumerate() {
+            let via = match line {
+                Some((dest, src, len)) => format!("map line {dest} {src} {len}"),
+                None => "no matching map line".to_string(),
+            };
+            result.push_str(&format!("{} {:?} -> {} by {}\n", self.categories[index], range, self.categories[index + 1], via));
+        }
+        result.push_str(&format!("{} {:?}\n", self.categories.last().unwrap(), trace.output));
+        result
+    }
+}
+
+fn process_input(input: &str, parse_as_ranges: bool) -> isize {
+    let (seeds, almanac) = parse_input(input, parse_as_ranges);
+    if !parse_as_ranges {
+        return seeds.iter().map(|&(seed, _)| almanac.forward(seed)).min().unwrap();
     }
-    mapped_ranges.iter().map(|x| x.0).min().unwrap()
+    let seeds = IntervalSet::from_ranges(seeds.iter().map(|&(start, len)| start..(start + len)));
+    almanac.map_ranges(&seeds).min().unwrap()
+}
+
+fn trace_min_location(input: &str) -> String {
+    let (seeds, almanac) = parse_input(input, true);
+    let seeds = IntervalSet::from_ranges(seeds.iter().map(|&(start, len)| start..(start + len)));
+    let traces = almanac.trace_ranges(&seeds);
+    almanac.format_trace(&traces[0])
+}
+
+fn parse_input(input: &str, parse_as_ranges: bool) -> (Vec<(isize, isize)>, Almanac) {
+    let mut sections = input.trim().split("\n\n").filter(|&x| !x.is_empty());
+    let seeds_section = sections.next().unwrap();
+    let Some(seeds_text) = seeds_section.strip_prefix("seeds:") else {
+        panic!("expected seeds section, got {}", seeds_section);
+    };
+    let seeds = parse_seeds(seeds_text, parse_as_ranges);
+    let almanac = Almanac::parse(sections);
+    if (almanac.categories[0] != "seed") || (almanac.categories.last().unwrap() != "location") {
+        panic!("almanac does not lead from seed to location: {:?}", almanac.categories);
+    }
+    (seeds, almanac)
 }
 
 #[test]
@@ -102,7 +204,7 @@ fn parse_seeds(text: &str, parse_as_ranges: bool) -> Vec<(isize, isize)> {
     let numbers = text
         .trim()
         .split([' ', '\n'])
-        .filter(|&x| x != "")
+        .filter(|&x| !x.is_empty())
         .map(|x| x.parse().unwrap())
         .collect::<Vec<_>>();
     if parse_as_ranges {
@@ -160,7 +262,7 @@ fn parse_map_line(line: &str) -> (isize, isize, isize) {
     let vec = line
         .trim()
         .split(' ')
-        .filter(|&x| x != "")
+        .filter(|&x| !x.is_empty())
         .map(|x| x.parse().unwrap())
         .collect::<Vec<_>>();
     if vec.len() != 3 {
@@ -175,59 +277,214 @@ fn test_parse_map_line() {
     assert_eq!((37, 52, 2), parse_map_line("37 52 2"));
 }
 
-fn map_ranges(ranges: &[(isize, isize)], maps: &[(isize, isize, isize)]) -> Vec<(isize, isize)> {
-    let mut result_ranges = vec![];
-    let mut this_iteration = vec![];
-    let mut next_iteration = ranges.to_owned();
-    for &map in maps {
-        (this_iteration, next_iteration) = (next_iteration, this_iteration);
-        for &range in this_iteration.iter() {
-            let range_end = range.0 + range.1 - 1;
-            let map_range_end = map.1 + map.2 - 1;
-            let size_before_map = map.1 - range.0;
-            let intersection_start = cmp::max(range.0, map.1);
-            let intersection_end = cmp::min(range_end, map_range_end);
-            let intersection_size = intersection_end - intersection_start + 1;
-            if intersection_size > 0 {
-                result_ranges.push((map.0 + cmp::max(range.0 - map.1, 0), intersection_size));
-                if size_before_map > 0 {
-                    next_iteration.push((range.0, size_before_map));
-                }
-                let size_after_map = range_end - map_range_end;
-                if size_after_map > 0 {
-                    next_iteration.push((intersection_end + 1, size_after_map));
-                }
-            } else {
-                next_iteration.push(range);
+fn get_stage_mapping(maps: &[MapLine]) -> Vec<MappingPiece> {
+    let mut mapping = vec![];
+    let mut not_mapped = IntervalSet::from(isize::MIN..isize::MAX);
+    for &(dest, src, len) in maps {
+        let map_range = IntervalSet::from(src..(src + len));
+        for range in not_mapped.intersection(&map_range).ranges() {
+            mapping.push(MappingPiece{range: range.clone(), offset: dest - src, lines: vec![Some((dest, src, len))]});
+        }
+        not_mapped = not_mapped.difference(&map_range);
+    }
+    mapping.extend(
+        not_mapped
+            .ranges()
+            .iter()
+            .map(|range| MappingPiece{range: range.clone(), offset: 0, lines: vec![None]})
+    );
+    mapping.sort_by_key(|piece| piece.range.start);
+    mapping
+}
+
+fn compose_mapping(first: &[MappingPiece], second: &[MappingPiece]) -> Vec<MappingPiece> {
+    let mut result = vec![];
+    for piece in first {
+        let offset = piece.offset;
+        let image = (piece.range.start + offset)..(piece.range.end + offset);
+        for second_piece in second {
+            let start = cmp::max(image.start, second_piece.range.start);
+            let end = cmp::min(image.end, second_piece.range.end);
+            if start < end {
+                let mut lines = piece.lines.clone();
+                lines.extend(&second_piece.lines);
+                result.push(MappingPiece{range: (start - offset)..(end - offset), offset: offset + second_piece.offset, lines});
             }
         }
-        this_iteration.truncate(0);
     }
-    result_ranges.append(&mut next_iteration);
-    result_ranges
+    result.sort_by_key(|piece| piece.range.start);
+    let mut merged: Vec<MappingPiece> = Vec::with_capacity(result.len());
+    for piece in result {
+        match merged.last_mut() {
+            Some(last) if (last.range.end == piece.range.start) && (last.lines == piece.lines) => last.range.end = piece.range.end,
+            _ => merged.push(piece),
+        }
+    }
+    merged
+}
+
+fn apply_mapping(ranges: &IntervalSet<isize>, mapping: &[MappingPiece]) -> IntervalSet<isize> {
+    let mut result = IntervalSet::new();
+    for piece in mapping {
+        let mapped = ranges.intersection(&IntervalSet::from(piece.range.clone())).shift(piece.offset);
+        result = result.union(&mapped);
+    }
+    result
 }
 
 #[test]
-fn test_map_ranges() {
-    assert_eq!([(5, 5)], map_ranges(&[(5, 5)], &[(20, 10, 5)])[..]);
-    assert_eq!([(20, 1), (5, 4)], map_ranges(&[(5, 5)], &[(20, 9, 5)])[..]);
-    assert_eq!([(20, 2), (5, 3)], map_ranges(&[(5, 5)], &[(20, 8, 5)])[..]);
-    assert_eq!([(20, 3), (5, 2)], map_ranges(&[(5, 5)], &[(20, 7, 5)])[..]);
-    assert_eq!([(20, 4), (5, 1)], map_ranges(&[(5, 5)], &[(20, 6, 5)])[..]);
-    assert_eq!([(20, 5)], map_ranges(&[(5, 5)], &[(20, 5, 5)])[..]);
-    assert_eq!([(21, 4), (9, 1)], map_ranges(&[(5, 5)], &[(20, 4, 5)])[..]);
-    assert_eq!([(22, 3), (8, 2)], map_ranges(&[(5, 5)], &[(20, 3, 5)])[..]);
-    assert_eq!([(23, 2), (7, 3)], map_ranges(&[(5, 5)], &[(20, 2, 5)])[..]);
-    assert_eq!([(24, 1), (6, 4)], map_ranges(&[(5, 5)], &[(20, 1, 5)])[..]);
-    assert_eq!([(5, 5)], map_ranges(&[(5, 5)], &[(20, 0, 5)])[..]);
-    assert_eq!([(22, 5)], map_ranges(&[(5, 5)], &[(20, 3, 10)])[..]);
-    assert_eq!([(20, 5), (3, 2), (10, 3)], map_ranges(&[(3, 10)], &[(20, 5, 5)])[..]);
-
-    let values = [(79, 1), (14, 1), (55, 1), (13, 1)];
+fn test_stage_mapping() {
+    let map_single = |range: (isize, isize), map: (isize, isize, isize)| {
+        let ranges = IntervalSet::from(range.0..(range.0 + range.1));
+        apply_mapping(&ranges, &get_stage_mapping(&[map]))
+            .ranges()
+            .iter()
+            .map(|x| (x.start, x.end - x.start))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!([(5, 5)], map_single((5, 5), (20, 10, 5))[..]);
+    assert_eq!([(5, 4), (20, 1)], map_single((5, 5), (20, 9, 5))[..]);
+    assert_eq!([(5, 3), (20, 2)], map_single((5, 5), (20, 8, 5))[..]);
+    assert_eq!([(5, 2), (20, 3)], map_single((5, 5), (20, 7, 5))[..]);
+    assert_eq!([(5, 1), (20, 4)], map_single((5, 5), (20, 6, 5))[..]);
+    assert_eq!([(20, 5)], map_single((5, 5), (20, 5, 5))[..]);
+    assert_eq!([(9, 1), (21, 4)], map_single((5, 5), (20, 4, 5))[..]);
+    assert_eq!([(8, 2), (22, 3)], map_single((5, 5), (20, 3, 5))[..]);
+    assert_eq!([(7, 3), (23, 2)], map_single((5, 5), (20, 2, 5))[..]);
+    assert_eq!([(6, 4), (24, 1)], map_single((5, 5), (20, 1, 5))[..]);
+    assert_eq!([(5, 5)], map_single((5, 5), (20, 0, 5))[..]);
+    assert_eq!([(22, 5)], map_single((5, 5), (20, 3, 10))[..]);
+    assert_eq!([(3, 2), (10, 3), (20, 5)], map_single((3, 10), (20, 5, 5))[..]);
+
+    let values = IntervalSet::from_ranges([79..80, 14..15, 55..56, 13..14]);
     let map = [
         (50, 98, 2),
         (52, 50, 48),
     ];
-    let expected = [(81, 1), (57, 1), (14, 1), (13, 1)];
-    assert_eq!(expected, map_ranges(&values, &map)[..]);
+    let expected = IntervalSet::from_ranges([81..82, 57..58, 14..15, 13..14]);
+    assert_eq!(expected, apply_mapping(&values, &get_stage_mapping(&map)));
+}
+
+#[test]
+fn test_almanac() {
+    let input = "
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+    let almanac = Almanac::parse(input.trim().split("\n\n"));
+    assert_eq!(8, almanac.categories.len());
+    assert_eq!("location", almanac.categories[7]);
+    assert_eq!(82, almanac.forward(79));
+    assert_eq!(43, almanac.forward(14));
+    assert_eq!(86, almanac.forward(55));
+    assert_eq!(35, almanac.forward(13));
+    assert_eq!(200, almanac.forward(200));
+    assert_eq!([79], almanac.inverse(82)[..]);
+    assert_eq!([82], almanac.inverse(46)[..]);
+    for seed in 0..120 {
+        assert_eq!([seed], almanac.inverse(almanac.forward(seed))[..]);
+    }
+    assert!(almanac.mapping.windows(2).all(|x| x[0].range.end == x[1].range.start));
+
+    let input = "
+b-to-c map:
+0 10 5
+
+a-to-b map:
+10 0 5
+";
+    let almanac = Almanac::parse(input.trim().split("\n\n"));
+    assert_eq!(["a", "b", "c"], almanac.categories[..]);
+    assert_eq!(3, almanac.forward(3));
+    assert_eq!(2, almanac.forward(12));
+    assert_eq!([2, 12], almanac.inverse(2)[..]);
+    assert!(almanac.inverse(12).is_empty());
+}
+
+#[test]
+fn test_trace_min_location() {
+    let input = "
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+    let (_, almanac) = parse_input(input, true);
+    let traces = almanac.trace_ranges(&IntervalSet::from_ranges([79..93, 55..68]));
+    assert_eq!(46, traces[0].output.start);
+    assert_eq!(82, traces[0].steps[0].0.start);
+    assert_eq!(Some((52, 50, 48)), traces[0].steps[0].1);
+    assert_eq!(None, traces[0].steps[1].1);
+    let total_len = traces.iter().map(|trace| trace.output.end - trace.output.start).sum::<isize>();
+    assert_eq!(27, total_len);
+
+    let expected = "
+seed 82..92 -> soil by map line 52 50 48
+soil 84..94 -> fertilizer by no matching map line
+fertilizer 84..94 -> water by no matching map line
+water 84..94 -> light by map line 18 25 70
+light 77..87 -> temperature by map line 45 77 23
+temperature 45..55 -> humidity by map line 1 0 69
+humidity 46..56 -> location by no matching map line
+location 46..56
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), trace_min_location(input));
 }
@@ -6,7 +6,7 @@ fn main() {
     println!("{}", result);
 }
 
-fn process_input(input: &str, is_one_number: bool) -> isize {
+fn process_input(input: &str, is_one_number: bool) -> u128 {
     let mut times = vec![];
     let mut distances = vec![];
     for line in input.trim().lines() {
@@ -23,7 +23,7 @@ fn process_input(input: &str, is_one_number: bool) -> isize {
     times.iter()
         .zip(distances.iter())
         .map(|(&time, &distance)| count_possible_wins(time, distance))
-        .fold(1, |res, x| res * x)
+        .product()
 }
 
 #[test]
@@ -38,7 +38,7 @@ Distance:  9  40  200
     assert_eq!(71503, result);
 }
 
-fn parse_line(line: &str, is_one_number: bool) -> Vec<isize> {
+fn parse_line(line: &str, is_one_number: bool) -> Vec<u128> {
     if is_one_number {
         let one_num = line
             .trim()
@@ -52,7 +52,7 @@ fn parse_line(line: &str, is_one_number: bool) -> Vec<isize> {
         line
             .trim()
             .split(' ')
-            .filter(|&x| x != "")
+            .filter(|&x| !x.is_empty())
             .map(|x| x.parse().unwrap())
             .collect()
     }
@@ -64,7 +64,7 @@ fn test_parse_line() {
     assert_eq!([71530], parse_line("      7  15   30", true)[..]);
 }
 
-fn count_possible_wins(time: isize, distance_to_beat: isize) -> isize {
+fn count_possible_wins(time: u128, distance_to_beat: u128) -> u128 {
     /*
     hold_time = 0..=time;
     my_dist = speed * time_left
@@ -83,21 +83,46 @@ fn count_possible_wins(time: isize, distance_to_beat: isize) -> isize {
     D = t^2 - 4(-1 * (-d)) = t^2 - 4d
     x = (-t +- sqrt(D)) / 2*(-1) = (t +- sqrt(D)) / 2
      */
-    let d = (time * time) - (4 * distance_to_beat);
-    if d < 0 {
-        panic!("impossible distance {} for time {}", distance_to_beat, time);
-    }
-    let hold_time_to_beat = ((time as f32) - (d as f32).sqrt()) / 2f32;
-    /*
-    min_hold_time should be a next int after hold_time_to_beat
-    so if hold_time_to_beat is 9.9, then min_hold_time should be 10
-    but if hold_time_to_beat is 10.0 then min_hold_time should be 11
-    which is not always correctly handled just by ceil
-     */
-    let mut min_hold_time = hold_time_to_beat.ceil() as isize;
-    if (min_hold_time * (time - min_hold_time)) <= distance_to_beat {
-        min_hold_time += 1;
+    // a product that does not fit is bigger than any distance
+    let is_beating = |hold_time: u128| hold_time.checked_mul(time - hold_time).is_none_or(|x| x > distance_to_beat);
+    let half_time = time / 2;
+    if !is_beating(half_time) {
+        return 0;
     }
+    let min_hold_time = match (time.checked_mul(time), distance_to_beat.checked_mul(4)) {
+        (Some(time_squared), Some(four_distance)) => {
+            let Some(d) = time_squared.checked_sub(four_distance) else {
+                return 0;
+            };
+            /*
+            integer sqrt is floor(sqrt(D)), so the estimate can be off from the real min_hold_time by one,
+            min_hold_time should be a next int after the root, so if the root is exactly 10 then min_hold_time should be 11
+             */
+            let mut min_hold_time = (time - d.isqrt()) / 2;
+            while !is_beating(min_hold_time) {
+                min_hold_time += 1;
+            }
+            while (min_hold_time > 0) && is_beating(min_hold_time - 1) {
+                min_hold_time -= 1;
+            }
+            min_hold_time
+        },
+        (Some(_), None) => return 0,
+        (None, _) => {
+            // time squared does not fit, so binary search where the distance grows, up to half time
+            let mut low = 0;
+            let mut high = half_time;
+            while low < high {
+                let middle = low + (high - low) / 2;
+                if is_beating(middle) {
+                    high = middle;
+                } else {
+                    low = middle + 1;
+                }
+            }
+            low
+        },
+    };
     /*
     total number of options is 0..=time, so count is time+1
     we need to get count of min_hold_time..=(time - min_hold_time)
@@ -112,3 +137,39 @@ fn test_count_possible_wins() {
     assert_eq!(8, count_possible_wins(15, 40));
     assert_eq!(9, count_possible_wins(30, 200));
 }
+
+#[test]
+fn test_count_possible_wins_against_brute_force() {
+    for time in 0..80u128 {
+        for distance in 0..(time * time / 4 + 3) {
+            let expected = (0..=time).filter(|hold_time| hold_time * (time - hold_time) > distance).count() as u128;
+            assert_eq!(expected, count_possible_wins(time, distance), "time {time} distance {distance}");
+        }
+    }
+}
+
+#[test]
+fn test_count_possible_wins_big() {
+    assert_eq!(71503, count_possible_wins(71530, 940200));
+    let time = u64::MAX as u128;
+    assert_eq!(time + 1, count_possible_wins(time, 0) + 2);
+    let half = time / 2;
+    assert_eq!(2, count_possible_wins(time, half * (time - half) - 1));
+    assert_eq!(0, count_possible_wins(time, half * (time - half)));
+    assert_eq!(0, count_possible_wins(1000, u128::MAX));
+}
+
+#[test]
+fn test_count_possible_wins_above_u64() {
+    let time = 1u128 << 70;
+    assert_eq!(time - 1, count_possible_wins(time, 0));
+    // half time squared is 2^128, one more than the biggest distance
+    assert_eq!(1, count_possible_wins(1 << 65, u128::MAX));
+    // one more step of time adds half time to the distance, so k * (k + 1) < 2^64 + 1 steps off half time win
+    assert_eq!(1 << 33, count_possible_wins((1 << 65) + 1, u128::MAX));
+    // only hold times within 4 of half time beat it
+    let time = (1u128 << 64) + 1024;
+    let half = time / 2;
+    assert_eq!(9, count_possible_wins(time, half * half - 25));
+    assert_eq!(12, count_possible_wins(time + 1, half * (half + 1) - 31));
+}
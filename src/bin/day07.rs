@@ -1,13 +1,19 @@
 use std::cmp::Ordering;
+use std::env;
 use std::fs::read_to_string;
 
 fn main() {
     let file_contents = read_to_string("inputs/day07.txt").unwrap();
-    let result = process_input(&file_contents, true);
+    let result = process_input(&file_contents, &RuleSet::standard());
     println!("{}", result);
+    let result = process_input(&file_contents, &RuleSet::with_jokers());
+    println!("{}", result);
+    if env::args().any(|x| x == "--explain") {
+        print!("{}", explain_input(&file_contents, &RuleSet::with_jokers()));
+    }
 }
 
-#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum HandType {
     HighCard,
     OnePair,
@@ -17,29 +23,158 @@ enum HandType {
     FourOfAKind,
     FiveOfAKind,
 }
+
 #[derive(PartialEq, Debug, Clone, Copy)]
-struct Hand([usize; 5]);
+enum TieBreaker {
+    CardsInOrder,
+    SortedCards,
+}
+
+#[derive(Debug)]
+struct RuleSet {
+    card_order: Vec<char>,
+    wildcards: Vec<char>,
+    type_order: Vec<HandType>,
+    tie_breaker: TieBreaker,
+}
+
+const STANDARD_TYPE_ORDER: [HandType; 7] = [
+    HandType::HighCard,
+    HandType::OnePair,
+    HandType::TwoPair,
+    HandType::ThreeOfAKind,
+    HandType::FullHouse,
+    HandType::FourOfAKind,
+    HandType::FiveOfAKind,
+];
+
+impl RuleSet {
+    fn standard() -> Self {
+        Self{
+            card_order: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            type_order: STANDARD_TYPE_ORDER.to_vec(),
+            tie_breaker: TieBreaker::CardsInOrder,
+        }
+    }
 
-#[derive(Debug, Clone, Copy)]
-struct HandWithType {
-    hand: Hand,
+    fn with_jokers() -> Self {
+        Self{
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            type_order: STANDARD_TYPE_ORDER.to_vec(),
+            tie_breaker: TieBreaker::CardsInOrder,
+        }
+    }
+
+    fn card_strength(&self, card: char) -> usize {
+        match self.card_order.iter().position(|&x| x == card) {
+            Some(strength) => strength,
+            None => panic!("unexpected card {}", card),
+        }
+    }
+
+    fn type_strength(&self, hand_type: HandType) -> usize {
+        match self.type_order.iter().position(|&x| x == hand_type) {
+            Some(strength) => strength,
+            None => panic!("hand type {:?} is not ranked", hand_type),
+        }
+    }
+
+    fn tie_break_cards(&self, cards: &[char]) -> Vec<usize> {
+        let mut strengths = cards.iter().map(|&x| self.card_strength(x)).collect::<Vec<_>>();
+        if self.tie_breaker == TieBreaker::SortedCards {
+            strengths.sort_by(|x, y| y.cmp(x));
+        }
+        strengths
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Hand<'a> {
+    cards: Vec<char>,
     hand_type: HandType,
+    rules: &'a RuleSet,
+}
+
+impl PartialEq for Hand<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Hand<'_> {}
+
+impl PartialOrd for Hand<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Hand<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rules = self.rules;
+        rules.type_strength(self.hand_type)
+            .cmp(&rules.type_strength(other.hand_type))
+            .then_with(|| rules.tie_break_cards(&self.cards).cmp(&rules.tie_break_cards(&other.cards)))
+    }
+}
+
+fn process_input(input: &str, rules: &RuleSet) -> usize {
+    let hands_and_bids = rank_hands(input, rules);
+    let mut total = 0;
+    for (index, &(_, bid)) in hands_and_bids.iter().enumerate() {
+        total += (index + 1) * bid;
+    }
+    total
 }
 
-fn process_input(input: &str, j_is_joker: bool) -> usize {
+fn rank_hands<'a>(input: &str, rules: &'a RuleSet) -> Vec<(Hand<'a>, usize)> {
     let mut hands_and_bids = vec![];
     for line in input.trim().lines() {
         let (hand, bid) = line.split_once(' ').unwrap();
-        let hand = parse_hand_with_type(hand, j_is_joker);
+        let hand = parse_hand(hand, rules);
         let bid = bid.parse::<usize>().unwrap();
         hands_and_bids.push((hand, bid));
     }
-    hands_and_bids.sort_by(|x, y| compare_hands(x.0, y.0));
+    hands_and_bids.sort_by(|x, y| x.0.cmp(&y.0));
+    hands_and_bids
+}
+
+fn explain_input(input: &str, rules: &RuleSet) -> String {
+    let mut result = String::new();
     let mut total = 0;
-    for (index, &(_, bid)) in hands_and_bids.iter().enumerate() {
-        total += (index + 1) * bid;
+    for (index, (hand, bid)) in rank_hands(input, rules).iter().enumerate() {
+        let rank = index + 1;
+        let winnings = rank * bid;
+        total += winnings;
+        let cards = hand.cards.iter().collect::<String>();
+        let (hand_type, substituted) = get_best_substitution(&hand.cards, rules);
+        let substituted = substituted.iter().collect::<String>();
+        result.push_str(&format!("rank {rank}: {cards} as {substituted} {hand_type:?}, bid {bid}, wins {rank} * {bid} = {winnings}\n"));
     }
-    total
+    result.push_str(&format!("total {total}\n"));
+    result
+}
+
+#[test]
+fn test_explain_input() {
+    let input = "
+32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+";
+    let expected = "
+rank 1: 32T3K as 32T3K OnePair, bid 765, wins 1 * 765 = 765
+rank 2: KK677 as KK677 TwoPair, bid 28, wins 2 * 28 = 56
+rank 3: T55J5 as T5555 FourOfAKind, bid 684, wins 3 * 684 = 2052
+rank 4: QQQJA as QQQQA FourOfAKind, bid 483, wins 4 * 483 = 1932
+rank 5: KTJJT as KTTTT FourOfAKind, bid 220, wins 5 * 220 = 1100
+total 5905
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), explain_input(input, &RuleSet::with_jokers()));
 }
 
 #[test]
@@ -51,167 +186,187 @@ KK677 28
 KTJJT 220
 QQQJA 483
 ";
-    let result = process_input(input, false);
+    let result = process_input(input, &RuleSet::standard());
     assert_eq!(6440, result);
 
-    let result = process_input(input, true);
+    let result = process_input(input, &RuleSet::with_jokers());
     assert_eq!(5905, result);
 }
 
-fn parse_hand_with_type(str: &str, j_is_joker: bool) -> HandWithType {
-    let hand = parse_hand(str, j_is_joker);
-    let hand_type = get_hand_type(hand);
-    HandWithType{hand, hand_type}
-}
-
-fn parse_hand(str: &str, j_is_joker: bool) -> Hand {
-    if str.len() < 5 {
-        panic!("hand too small {} {}", str.len(), str);
+fn parse_hand<'a>(str: &str, rules: &'a RuleSet) -> Hand<'a> {
+    let cards = str.chars().collect::<Vec<_>>();
+    if cards.len() != 5 {
+        panic!("unexpected hand size {} {}", cards.len(), str);
     }
-    let mut chars = str.chars();
-    Hand([
-        parse_card(chars.next().unwrap(), j_is_joker),
-        parse_card(chars.next().unwrap(), j_is_joker),
-        parse_card(chars.next().unwrap(), j_is_joker),
-        parse_card(chars.next().unwrap(), j_is_joker),
-        parse_card(chars.next().unwrap(), j_is_joker),
-    ])
+    for &card in cards.iter() {
+        rules.card_strength(card);
+    }
+    let hand_type = get_hand_type(&cards, rules);
+    Hand{cards, hand_type, rules}
 }
 
 #[test]
 fn test_parse_hand() {
-    assert_eq!(Hand([3, 2, 10, 3, 13]), parse_hand("32T3K", false));
-    assert_eq!(Hand([11, 11, 11, 11, 11]), parse_hand("JJJJJ", false));
-    assert_eq!(Hand([0, 0, 0, 0, 0]), parse_hand("JJJJJ", true));
+    let rules = RuleSet::standard();
+    assert_eq!(['3', '2', 'T', '3', 'K'], parse_hand("32T3K", &rules).cards[..]);
+    assert_eq!(HandType::OnePair, parse_hand("32T3K", &rules).hand_type);
 }
 
-fn parse_card(char: char, j_is_joker: bool) -> usize {
-    // not using FromStr for simplicity
-    match char {
-        '2'..='9' => char.to_digit(10).unwrap() as usize,
-        'T' => 10,
-        'J' => if j_is_joker {0} else {11},
-        'Q' => 12,
-        'K' => 13,
-        'A' => 14,
-        _ => panic!("unexpected card {}", char),
-    }
+#[test]
+fn test_card_strength() {
+    let rules = RuleSet::standard();
+    assert_eq!(0, rules.card_strength('2'));
+    assert_eq!(7, rules.card_strength('9'));
+    assert_eq!(8, rules.card_strength('T'));
+    assert_eq!(12, rules.card_strength('A'));
+    assert_eq!(9, rules.card_strength('J'));
+    assert_eq!(0, RuleSet::with_jokers().card_strength('J'));
 }
 
-#[test]
-fn test_parse_card() {
-    assert_eq!(2, parse_card('2', false));
-    assert_eq!(9, parse_card('9', false));
-    assert_eq!(10, parse_card('T', false));
-    assert_eq!(14, parse_card('A', false));
-    assert_eq!(11, parse_card('J', false));
-    assert_eq!(0, parse_card('J', true));
-}
-
-fn get_hand_type(hand: Hand) -> HandType {
-    /*
-    0, 1, 2, 3, 4
-    5 of a kind - 4 + 3 + 2 + 1 = 10 matches    cant have jokers
-    4 of a kind - 3 + 2 + 1 = 6 matches         + joker - 5 of a kind (10 matches)
-    full house - 2 + 1 + 1 = 4 matches          cant have jokers
-    3 of a kind - 2 + 1 = 3 matches             + joker - 4 of a kind (6 matches)
-    2 pair - 1 + 1 = 2 matches                  + joker - full house (4 matches)
-    1 pair - 1 match                            + joker - 3 of a kind (3 matches)
-    high card - 0 matches                       + joker - 1 pair (1 matches)
-     */
-    let mut matches_count = 0;
-    let mut jokers_count = 0;
-    for (first_card_index, &first_card) in hand.0.iter().enumerate() {
-        if first_card == 0 {
-            jokers_count += 1;
-        } else {
-            for &second_card in hand.0.iter().skip(first_card_index + 1) {
-                if first_card == second_card {
-                    matches_count += 1;
-                }
-            }
+fn get_hand_type(cards: &[char], rules: &RuleSet) -> HandType {
+    // wildcards always give the best hand by joining the biggest group of the other cards
+    let mut group_sizes = vec![];
+    let mut counted = vec![];
+    let mut wildcards_count = 0;
+    for &card in cards.iter() {
+        if rules.wildcards.contains(&card) {
+            wildcards_count += 1;
+        } else if !counted.contains(&card) {
+            counted.push(card);
+            group_sizes.push(cards.iter().filter(|&&x| x == card).count());
         }
     }
-    match (matches_count, jokers_count) {
-        (10, 0) => HandType::FiveOfAKind,
-        (6, 0) => HandType::FourOfAKind,
-        (6, 1) => HandType::FiveOfAKind,
-        (4, 0) => HandType::FullHouse,
-        (3, 0) => HandType::ThreeOfAKind,
-        (3, 1) => HandType::FourOfAKind,
-        (3, 2) => HandType::FiveOfAKind,
-        (2, 0) => HandType::TwoPair,
-        (2, 1) => HandType::FullHouse,
-        (1, 0) => HandType::OnePair,
-        (1, 1) => HandType::ThreeOfAKind,
-        (1, 2) => HandType::FourOfAKind,
-        (1, 3) => HandType::FiveOfAKind,
-        (0, 0) => HandType::HighCard,
-        (0, 1) => HandType::OnePair,
-        (0, 2) => HandType::ThreeOfAKind,
-        (0, 3) => HandType::FourOfAKind,
-        (0, 4) => HandType::FiveOfAKind,
-        (0, 5) => HandType::FiveOfAKind,
-        _ => panic!("strange hand with {} matches and {} jokers {:?}", matches_count, jokers_count, hand),
+    group_sizes.sort_by(|x, y| y.cmp(x));
+    if group_sizes.is_empty() {
+        group_sizes.push(0);
     }
+    group_sizes[0] += wildcards_count;
+    match group_sizes[..] {
+        [5] => HandType::FiveOfAKind,
+        [4, 1] => HandType::FourOfAKind,
+        [3, 2] => HandType::FullHouse,
+        [3, 1, 1] => HandType::ThreeOfAKind,
+        [2, 2, 1] => HandType::TwoPair,
+        [2, 1, 1, 1] => HandType::OnePair,
+        [1, 1, 1, 1, 1] => HandType::HighCard,
+        _ => panic!("strange hand with groups {:?} {:?}", group_sizes, cards),
+    }
+}
+
+// replaces the wildcards with the concrete cards that give the best hand type
+fn get_best_substitution(cards: &[char], rules: &RuleSet) -> (HandType, Vec<char>) {
+    let target = cards
+        .iter()
+        .filter(|x| !rules.wildcards.contains(x))
+        .max_by_key(|&&card| (cards.iter().filter(|&&x| x == card).count(), rules.card_strength(card)))
+        .or_else(|| rules.card_order.iter().rev().find(|x| !rules.wildcards.contains(x)));
+    let substituted = cards
+        .iter()
+        .map(
+            |&card|
+                match target {
+                    Some(&target) if rules.wildcards.contains(&card) => target,
+                    _ => card,
+                }
+        )
+        .collect();
+    (get_hand_type(cards, rules), substituted)
 }
 
 #[test]
-fn test_get_hand_type() {
-    let j_is_joker = false;
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("22222", j_is_joker).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("22223", j_is_joker).hand_type);
-    assert_eq!(HandType::FullHouse, parse_hand_with_type("22233", j_is_joker).hand_type);
-    assert_eq!(HandType::ThreeOfAKind, parse_hand_with_type("22234", j_is_joker).hand_type);
-    assert_eq!(HandType::TwoPair, parse_hand_with_type("22334", j_is_joker).hand_type);
-    assert_eq!(HandType::OnePair, parse_hand_with_type("22345", j_is_joker).hand_type);
-    assert_eq!(HandType::HighCard, parse_hand_with_type("23456", j_is_joker).hand_type);
-
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("2222J", false).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("2222J", true).hand_type);
-    assert_eq!(HandType::FullHouse, parse_hand_with_type("222JJ", false).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("222JJ", true).hand_type);
-    assert_eq!(HandType::ThreeOfAKind, parse_hand_with_type("2223J", false).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("2223J", true).hand_type);
-    assert_eq!(HandType::FullHouse, parse_hand_with_type("22JJJ", false).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("22JJJ", true).hand_type);
-    assert_eq!(HandType::TwoPair, parse_hand_with_type("223JJ", false).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("223JJ", true).hand_type);
-    assert_eq!(HandType::TwoPair, parse_hand_with_type("2233J", false).hand_type);
-    assert_eq!(HandType::FullHouse, parse_hand_with_type("2233J", true).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("2JJJJ", false).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("2JJJJ", true).hand_type);
-    assert_eq!(HandType::ThreeOfAKind, parse_hand_with_type("23JJJ", false).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("23JJJ", true).hand_type);
-    assert_eq!(HandType::TwoPair, parse_hand_with_type("233JJ", false).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("233JJ", true).hand_type);
-    assert_eq!(HandType::ThreeOfAKind, parse_hand_with_type("2333J", false).hand_type);
-    assert_eq!(HandType::FourOfAKind, parse_hand_with_type("2333J", true).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("JJJJJ", false).hand_type);
-    assert_eq!(HandType::FiveOfAKind, parse_hand_with_type("JJJJJ", true).hand_type);
-}
-
-fn compare_hands(hand1: HandWithType, hand2: HandWithType) -> Ordering {
-    let type_order = hand1.hand_type.partial_cmp(&hand2.hand_type).unwrap();
-    if type_order != Ordering::Equal {
-        return type_order;
-    }
-    for (index, &card1) in hand1.hand.0.iter().enumerate() {
-        let card2 = hand2.hand.0[index];
-        let card_order = card1.partial_cmp(&card2).unwrap();
-        if card_order != Ordering::Equal {
-            return card_order;
-        }
+fn test_get_best_substitution() {
+    let jokers = RuleSet::with_jokers();
+    assert_eq!((HandType::FiveOfAKind, "AAAAA".chars().collect()), get_best_substitution(&['J'; 5], &jokers));
+    assert_eq!((HandType::FullHouse, "22333".chars().collect()), get_best_substitution(&['2', '2', '3', '3', 'J'], &jokers));
+    assert_eq!((HandType::OnePair, "234AA".chars().collect()), get_best_substitution(&['2', '3', '4', 'J', 'A'], &jokers));
+    assert_eq!((HandType::TwoPair, "22334".chars().collect()), get_best_substitution(&['2', '2', '3', '3', '4'], &jokers));
+    let standard = RuleSet::standard();
+    assert_eq!((HandType::FiveOfAKind, "JJJJJ".chars().collect()), get_best_substitution(&['J'; 5], &standard));
+
+    let rules = RuleSet{
+        card_order: "J2345".chars().collect(),
+        wildcards: vec!['J', '2'],
+        type_order: STANDARD_TYPE_ORDER.to_vec(),
+        tie_breaker: TieBreaker::CardsInOrder,
+    };
+    for hand in ["J2345", "J2J23", "22J22", "34J45"] {
+        let cards = hand.chars().collect::<Vec<_>>();
+        let (hand_type, substituted) = get_best_substitution(&cards, &rules);
+        assert!(substituted.iter().all(|x| !rules.wildcards.contains(x)));
+        assert_eq!(hand_type, get_hand_type(&substituted, &rules));
     }
-    Ordering::Equal
+}
+
+#[test]
+fn test_get_hand_type() {
+    let standard = RuleSet::standard();
+    let jokers = RuleSet::with_jokers();
+    assert_eq!(HandType::FiveOfAKind, parse_hand("22222", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("22223", &standard).hand_type);
+    assert_eq!(HandType::FullHouse, parse_hand("22233", &standard).hand_type);
+    assert_eq!(HandType::ThreeOfAKind, parse_hand("22234", &standard).hand_type);
+    assert_eq!(HandType::TwoPair, parse_hand("22334", &standard).hand_type);
+    assert_eq!(HandType::OnePair, parse_hand("22345", &standard).hand_type);
+    assert_eq!(HandType::HighCard, parse_hand("23456", &standard).hand_type);
+
+    assert_eq!(HandType::FourOfAKind, parse_hand("2222J", &standard).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("2222J", &jokers).hand_type);
+    assert_eq!(HandType::FullHouse, parse_hand("222JJ", &standard).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("222JJ", &jokers).hand_type);
+    assert_eq!(HandType::ThreeOfAKind, parse_hand("2223J", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("2223J", &jokers).hand_type);
+    assert_eq!(HandType::FullHouse, parse_hand("22JJJ", &standard).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("22JJJ", &jokers).hand_type);
+    assert_eq!(HandType::TwoPair, parse_hand("223JJ", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("223JJ", &jokers).hand_type);
+    assert_eq!(HandType::TwoPair, parse_hand("2233J", &standard).hand_type);
+    assert_eq!(HandType::FullHouse, parse_hand("2233J", &jokers).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("2JJJJ", &standard).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("2JJJJ", &jokers).hand_type);
+    assert_eq!(HandType::ThreeOfAKind, parse_hand("23JJJ", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("23JJJ", &jokers).hand_type);
+    assert_eq!(HandType::TwoPair, parse_hand("233JJ", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("233JJ", &jokers).hand_type);
+    assert_eq!(HandType::ThreeOfAKind, parse_hand("2333J", &standard).hand_type);
+    assert_eq!(HandType::FourOfAKind, parse_hand("2333J", &jokers).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("JJJJJ", &standard).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("JJJJJ", &jokers).hand_type);
 }
 
 #[test]
 fn test_compare_hands() {
-    let j_is_joker = false;
-    assert_eq!(Ordering::Equal, compare_hands(parse_hand_with_type("22222", j_is_joker), parse_hand_with_type("22222", j_is_joker)));
-    assert_eq!(Ordering::Greater, compare_hands(parse_hand_with_type("22222", j_is_joker), parse_hand_with_type("22223", j_is_joker)));
-    assert_eq!(Ordering::Less, compare_hands(parse_hand_with_type("22223", j_is_joker), parse_hand_with_type("22222", j_is_joker)));
-    assert_eq!(Ordering::Greater, compare_hands(parse_hand_with_type("33333", j_is_joker), parse_hand_with_type("22222", j_is_joker)));
-    assert_eq!(Ordering::Less, compare_hands(parse_hand_with_type("22222", j_is_joker), parse_hand_with_type("33333", j_is_joker)));
+    let rules = RuleSet::standard();
+    assert_eq!(Ordering::Equal, parse_hand("22222", &rules).cmp(&parse_hand("22222", &rules)));
+    assert_eq!(Ordering::Greater, parse_hand("22222", &rules).cmp(&parse_hand("22223", &rules)));
+    assert_eq!(Ordering::Less, parse_hand("22223", &rules).cmp(&parse_hand("22222", &rules)));
+    assert_eq!(Ordering::Greater, parse_hand("33333", &rules).cmp(&parse_hand("22222", &rules)));
+    assert_eq!(Ordering::Less, parse_hand("22222", &rules).cmp(&parse_hand("33333", &rules)));
+}
+
+#[test]
+fn test_custom_rules() {
+    let rules = RuleSet{
+        card_order: "J2345".chars().collect(),
+        wildcards: vec!['J', '2'],
+        type_order: STANDARD_TYPE_ORDER.to_vec(),
+        tie_breaker: TieBreaker::CardsInOrder,
+    };
+    assert_eq!(HandType::FiveOfAKind, parse_hand("J2J23", &rules).hand_type);
+    assert_eq!(HandType::ThreeOfAKind, parse_hand("J2345", &rules).hand_type);
+    assert_eq!(HandType::FiveOfAKind, parse_hand("2222J", &rules).hand_type);
+
+    let rules = RuleSet{
+        tie_breaker: TieBreaker::SortedCards,
+        ..RuleSet::standard()
+    };
+    assert_eq!(Ordering::Greater, parse_hand("2345A", &rules).cmp(&parse_hand("K2345", &rules)));
+    assert_eq!(Ordering::Equal, parse_hand("AKQJ9", &rules).cmp(&parse_hand("9JQKA", &rules)));
+
+    let mut type_order = STANDARD_TYPE_ORDER.to_vec();
+    type_order.swap(4, 5);
+    let rules = RuleSet{
+        type_order,
+        ..RuleSet::standard()
+    };
+    assert_eq!(Ordering::Greater, parse_hand("22233", &rules).cmp(&parse_hand("AAAAK", &rules)));
 }
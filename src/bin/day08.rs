@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fs::read_to_string;
+use std::iter;
 
 fn main() {
     let file_contents = read_to_string("inputs/day08.txt").unwrap();
@@ -12,17 +13,12 @@ fn process_input(input: &str) -> usize {
     let moves = moves.trim().chars().collect::<Vec<_>>();
     let map = parse_map(map_str);
 
-    /*
-    the description does not explicitly state this, but each path reaches Z after a set amount of iterations
-    i.e. while following a path you can't encounter a Z after 3 steps, then after 8 more, then after 2 more.
-    if you've encountered a Z after 3 steps, then the next one will be after 3 more, and then after 3 more again
-    which is what makes this solution possible
-     */
-    map
+    let cycles = map
         .keys()
         .filter(|x| x.ends_with('A'))
-        .map(|&x| count_iterations_until_z(&moves, x, &map))
-        .fold(1, least_common_multiple)
+        .map(|&x| analyze_walk(&moves, x, &map))
+        .collect::<Vec<_>>();
+    find_first_common_z(&cycles).unwrap_or_else(|| panic!("walkers never reach Z at the same time"))
 }
 
 #[test]
@@ -78,39 +74,212 @@ fn parse_map(input: &str) -> HashMap<&str, (&str, &str)> {
     map
 }
 
-fn count_iterations_until_z(moves: &[char], starting_key: &str, map: &HashMap<&str, (&str, &str)>) -> usize {
+#[derive(Debug, Clone, PartialEq)]
+struct WalkCycle {
+    prefix: usize,
+    period: usize,
+    z_steps: Vec<usize>,
+}
+
+// follows the walker until its (node, move index) state repeats,
+// z_steps contains every step before prefix + period at which the walker is on a Z node
+fn analyze_walk(moves: &[char], starting_key: &str, map: &HashMap<&str, (&str, &str)>) -> WalkCycle {
+    let mut visited = HashMap::new();
+    let mut z_steps = vec![];
     let mut current_key = starting_key;
-    let mut iter_count = 0usize;
-    'outer: loop {
-        for &move_char in moves.iter() {
-            if current_key.ends_with('Z') {
-                break 'outer;
-            }
-            if iter_count > 1000000 {
-                panic!("too many iterations for starting key {}", starting_key);
-            }
-            iter_count += 1;
-            let next = map.get(current_key).unwrap();
-            current_key = match move_char {
-                'L' => next.0,
-                'R' => next.1,
-                _ => unreachable!(),
+    let mut step = 0usize;
+    loop {
+        let move_index = step % moves.len();
+        if let Some(&first_step) = visited.get(&(current_key, move_index)) {
+            return WalkCycle{prefix: first_step, period: step - first_step, z_steps};
+        }
+        visited.insert((current_key, move_index), step);
+        if current_key.ends_with('Z') {
+            z_steps.push(step);
+        }
+        if step > 10000000 {
+            panic!("too many iterations for starting key {}", starting_key);
+        }
+        let Some(next) = map.get(current_key) else {
+            panic!("unknown key {} on path from {}", current_key, starting_key);
+        };
+        current_key = match moves[move_index] {
+            'L' => next.0,
+            'R' => next.1,
+            move_char => panic!("unexpected move {}", move_char),
+        };
+        step += 1;
+    }
+}
+
+fn is_z_step(cycle: &WalkCycle, step: usize) -> bool {
+    let step = if step < cycle.prefix {
+        step
+    } else {
+        cycle.prefix + (step - cycle.prefix) % cycle.period
+    };
+    cycle.z_steps.binary_search(&step).is_ok()
+}
+
+const MAX_CANDIDATES: usize = 1000000;
+
+fn find_first_common_z(cycles: &[WalkCycle]) -> Option<usize> {
+    let max_prefix = cycles.iter().map(|x| x.prefix).max()?;
+    // before every walker has entered its cycle just check the steps one by one
+    if let Some(step) = (0..max_prefix).find(|&step| cycles.iter().all(|cycle| is_z_step(cycle, step))) {
+        return Some(step);
+    }
+
+    let mut candidates = vec![(0, 1)];
+    let mut modulus = 1;
+    for (index, cycle) in cycles.iter().enumerate() {
+        let Some(next_modulus) = get_lcm(modulus, cycle.period) else {
+            return find_common_z_past_overflow(&candidates, &cycles[index..], max_prefix);
+        };
+        modulus = next_modulus;
+        let mut next_candidates = vec![];
+        for &candidate in candidates.iter() {
+            for &z_step in cycle.z_steps.iter().filter(|&&x| x >= cycle.prefix) {
+                if let Some(merged) = merge_congruences(candidate, (z_step % cycle.period, cycle.period)) {
+                    next_candidates.push(merged);
+                }
             }
         }
+        next_candidates.sort();
+        next_candidates.dedup();
+        if next_candidates.len() > MAX_CANDIDATES {
+            // too many combinations of Z steps to keep, so walk the steps instead
+            return find_common_z_by_steps(cycles, max_prefix);
+        }
+        candidates = next_candidates;
+    }
+    candidates
+        .into_iter()
+        .filter_map(|(residue, modulus)| get_first_step_from(residue, modulus, max_prefix))
+        .min()
+}
+
+// first step that is residue (mod modulus) and not below max_prefix, if it fits
+fn get_first_step_from(residue: usize, modulus: usize, max_prefix: usize) -> Option<usize> {
+    if residue >= max_prefix {
+        Some(residue)
+    } else {
+        (max_prefix - residue).div_ceil(modulus).checked_mul(modulus)?.checked_add(residue)
+    }
+}
+
+fn get_lcm(first: usize, second: usize) -> Option<usize> {
+    let (gcd, _, _) = extended_gcd(first as i128, second as i128);
+    (first / gcd as usize).checked_mul(second)
+}
+
+/*
+the lcm with the period of cycles[0] does not fit, so each candidate has at most one step below usize::MAX
+that also is a Z step of cycles[0], and it is among the first cycles[0].period steps of that candidate,
+which leaves only checking those few steps against the rest of the cycles
+ */
+fn find_common_z_past_overflow(candidates: &[(usize, usize)], cycles: &[WalkCycle], max_prefix: usize) -> Option<usize> {
+    candidates
+        .iter()
+        .filter_map(|&(residue, modulus)| Some((get_first_step_from(residue, modulus, max_prefix)?, modulus)))
+        .flat_map(|(first_step, modulus)| iter::successors(Some(first_step), move |x| x.checked_add(modulus)).take(cycles[0].period))
+        .filter(|&step| cycles.iter().all(|cycle| is_z_step(cycle, step)))
+        .min()
+}
+
+// from max_prefix on all walkers are in their cycles, so after the lcm of the periods everything repeats
+fn find_common_z_by_steps(cycles: &[WalkCycle], max_prefix: usize) -> Option<usize> {
+    let combined_period = cycles.iter().try_fold(1, |period, cycle| get_lcm(period, cycle.period));
+    let end = combined_period.map_or(usize::MAX, |x| max_prefix.saturating_add(x));
+    (max_prefix..end).find(|&step| cycles.iter().all(|cycle| is_z_step(cycle, step)))
+}
+
+// combines x = first.0 (mod first.1) and x = second.0 (mod second.1) into a single congruence,
+// the lcm of the moduli has to fit into usize
+fn merge_congruences(first: (usize, usize), second: (usize, usize)) -> Option<(usize, usize)> {
+    let (residue1, modulus1) = (first.0 as i128, first.1 as i128);
+    let (residue2, modulus2) = (second.0 as i128, second.1 as i128);
+    let (gcd, inverse, _) = extended_gcd(modulus1, modulus2);
+    let diff = residue2 - residue1;
+    if diff % gcd != 0 {
+        return None;
     }
-    iter_count
+    let modulus = modulus1 / gcd * modulus2;
+    let step_modulus = modulus2 / gcd;
+    let steps = ((diff / gcd) % step_modulus * inverse).rem_euclid(step_modulus);
+    let residue = (residue1 + modulus1 * steps).rem_euclid(modulus);
+    let modulus = usize::try_from(modulus).unwrap_or_else(|_| panic!("merged modulus {} does not fit", modulus));
+    Some((residue as usize, modulus))
 }
 
-fn least_common_multiple(a: usize, b: usize) -> usize {
-    (a / greatest_common_divisor(a, b)) * b
+#[test]
+fn test_find_first_common_z_past_u64() {
+    // coprime periods with a product above u64::MAX
+    let periods = [9001, 9007, 9011, 9013, 9029];
+    let make_cycles = |target: usize| {
+        periods.map(|period| WalkCycle{prefix: 0, period, z_steps: vec![target % period]})
+    };
+    let target = (1 << 63) + 12345;
+    assert_eq!(Some(target), find_first_common_z(&make_cycles(target)));
+    assert_eq!(Some(17), find_first_common_z(&make_cycles(17)));
+    // the only common step is past u64::MAX
+    let cycles = periods
+        .iter()
+        .zip(1..)
+        .map(|(&period, z_step)| WalkCycle{prefix: 0, period, z_steps: vec![z_step]})
+        .collect::<Vec<_>>();
+    assert_eq!(None, find_first_common_z(&cycles));
 }
 
-fn greatest_common_divisor(a: usize, b: usize) -> usize {
-    let mut b = b;
-    let mut a = a;
-    while b != 0 {
-        (a, b) = (b, a % b);
+#[test]
+fn test_merge_congruences() {
+    assert_eq!(Some((5, 12)), merge_congruences((2, 3), (1, 4)));
+    assert_eq!(Some((4, 12)), merge_congruences((0, 4), (4, 6)));
+    assert_eq!(None, merge_congruences((1, 4), (2, 6)));
+    assert_eq!(Some((3, 5)), merge_congruences((0, 1), (3, 5)));
+}
+
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (gcd, x, y) = extended_gcd(b, a % b);
+        (gcd, y, x - (a / b) * y)
     }
-    a
 }
 
+#[test]
+fn test_analyze_walk() {
+    let input = "
+L
+
+1A = (1B, 1B)
+1B = (1Z, 1Z)
+1Z = (1C, 1C)
+1C = (1B, 1B)
+2A = (2Z, 2Z)
+2Z = (2B, 2B)
+2B = (2C, 2C)
+2C = (2D, 2D)
+2D = (2Z, 2Z)
+3A = (3Z, 3Z)
+3Z = (3B, 3B)
+3B = (3B, 3B)
+";
+    let (moves, map_str) = input.trim().split_once('\n').unwrap();
+    let moves = moves.trim().chars().collect::<Vec<_>>();
+    let map = parse_map(map_str);
+    let first = analyze_walk(&moves, "1A", &map);
+    assert_eq!(WalkCycle{prefix: 1, period: 3, z_steps: vec![2]}, first);
+    let second = analyze_walk(&moves, "2A", &map);
+    assert_eq!(WalkCycle{prefix: 1, period: 4, z_steps: vec![1]}, second);
+    let third = analyze_walk(&moves, "3A", &map);
+    assert_eq!(WalkCycle{prefix: 2, period: 1, z_steps: vec![1]}, third);
+
+    // the first Z steps are 2 and 1, so the lcm of them would give a wrong answer
+    assert_eq!(Some(5), find_first_common_z(&[first.clone(), second.clone()]));
+    assert_eq!(Some(5), find_common_z_by_steps(&[first.clone(), second.clone()], 1));
+    assert_eq!(Some(1), find_first_common_z(&[second, third.clone()]));
+    assert_eq!(None, find_first_common_z(&[first.clone(), third.clone()]));
+    assert_eq!(None, find_common_z_by_steps(&[first, third], 2));
+}
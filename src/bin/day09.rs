@@ -30,29 +30,52 @@ fn test_process_input() {
     assert_eq!(2, result);
 }
 
+#[derive(Debug, PartialEq)]
+enum ExtrapolationError {
+    NotPolynomial,
+    Overflow,
+}
+
 fn predict_next_value(sequence: &[isize], is_predict_back: bool) -> isize {
-    let mut all_diffs = vec![sequence.to_owned()];
-    let mut iter_count = 0usize;
-    loop {
-        let prev_diffs = all_diffs.last().unwrap();
-        if prev_diffs.iter().all(|&x| x == 0) {
-            break;
-        }
-        iter_count += 1;
-        if iter_count > 100000 {
-            panic!("too many iterations for sequence {:?}", sequence);
-        }
-        let mut diffs = vec![];
-        for i in 0..(prev_diffs.len() - 1) {
-            diffs.push(prev_diffs[i + 1] - prev_diffs[i]);
+    let index = if is_predict_back { -1 } else { sequence.len() as i128 };
+    predict_value_at(sequence, index)
+        .and_then(|x| isize::try_from(x).map_err(|_| ExtrapolationError::Overflow))
+        .unwrap_or_else(|error| panic!("can't predict value for sequence {:?}: {:?}", sequence, error))
+}
+
+// value at any index, where the sequence itself is at indexes 0..len
+fn predict_value_at(sequence: &[isize], index: i128) -> Result<i128, ExtrapolationError> {
+    let coefficients = get_newton_coefficients(sequence)?;
+    // newton forward form: f(index) = sum of coefficients[k] * binomial(index, k)
+    let mut binomial = 1i128;
+    let mut result = 0i128;
+    for (k, &coefficient) in coefficients.iter().enumerate() {
+        let term = coefficient.checked_mul(binomial).ok_or(ExtrapolationError::Overflow)?;
+        result = result.checked_add(term).ok_or(ExtrapolationError::Overflow)?;
+        // the next binomial is not needed after the last coefficient, and could overflow on its own
+        if k + 1 < coefficients.len() {
+            let k = k as i128;
+            binomial = binomial.checked_mul(index - k).ok_or(ExtrapolationError::Overflow)? / (k + 1);
         }
-        all_diffs.push(diffs);
     }
-    if is_predict_back {
-        all_diffs.iter().rev().fold(0, |acc, x| x[0] - acc)
-    } else {
-        all_diffs.iter().map(|x| x.last().unwrap()).sum()
+    Ok(result)
+}
+
+// first value of each row of the difference table, up to the last non-zero row
+fn get_newton_coefficients(sequence: &[isize]) -> Result<Vec<i128>, ExtrapolationError> {
+    let mut diffs = sequence.iter().map(|&x| x as i128).collect::<Vec<_>>();
+    let mut coefficients = vec![];
+    while !diffs.is_empty() {
+        if diffs.iter().all(|&x| x == 0) {
+            return Ok(coefficients);
+        }
+        coefficients.push(diffs[0]);
+        diffs = diffs
+            .windows(2)
+            .map(|pair| pair[1].checked_sub(pair[0]).ok_or(ExtrapolationError::Overflow))
+            .collect::<Result<_, _>>()?;
     }
+    Err(ExtrapolationError::NotPolynomial)
 }
 
 #[test]
@@ -64,3 +87,25 @@ fn test_predict_next_value() {
     assert_eq!(0, predict_next_value(&[1, 3, 6, 10, 15, 21], true));
     assert_eq!(5, predict_next_value(&[10, 13, 16, 21, 30, 45], true));
 }
+
+#[test]
+fn test_predict_value_at() {
+    let sequence = [10, 13, 16, 21, 30, 45];
+    assert_eq!(Ok(68), predict_value_at(&sequence, 6));
+    assert_eq!(Ok(5), predict_value_at(&sequence, -1));
+    assert_eq!(Ok(30), predict_value_at(&sequence, 4));
+
+    let cubic = |n: i128| n * n * n - 2 * n + 7;
+    let sequence = (0..6).map(|n| cubic(n) as isize).collect::<Vec<_>>();
+    assert_eq!(Ok(cubic(1000)), predict_value_at(&sequence, 1000));
+    assert_eq!(Ok(cubic(-5)), predict_value_at(&sequence, -5));
+    assert_eq!(Ok(cubic(1000000000)), predict_value_at(&sequence, 1000000000));
+    assert_eq!(Ok(1 << 70), predict_value_at(&[0, 1, 2], 1 << 70));
+
+    assert_eq!(Err(ExtrapolationError::NotPolynomial), predict_value_at(&[1, 2, 4, 8, 16], 5));
+    assert_eq!(Err(ExtrapolationError::NotPolynomial), predict_value_at(&[1, 2], 2));
+    assert_eq!(Ok(7), predict_value_at(&[7, 7], 100));
+    assert_eq!(Err(ExtrapolationError::NotPolynomial), predict_value_at(&[], 3));
+    assert_eq!(Err(ExtrapolationError::Overflow), predict_value_at(&sequence, i128::MAX / 2));
+    assert_eq!(Ok(isize::MAX as i128 + 1), predict_value_at(&[isize::MAX - 2, isize::MAX - 1, isize::MAX], 3));
+}
@@ -1,10 +1,14 @@
+use std::env;
 use std::fs::read_to_string;
 
 fn main() {
     let file_contents = read_to_string("inputs/day10.txt").unwrap();
-    let (part1, part2) = process_input(&file_contents);
+    let (part1, part2) = process_input(&file_contents).unwrap();
     println!("{}", part1);
     println!("{}", part2);
+    if env::args().any(|x| x == "--render") {
+        print!("{}", render_input(&file_contents).unwrap());
+    }
 }
 
 const TOP_BOTTOM: char = '|';
@@ -16,6 +20,26 @@ const BOTTOM_LEFT: char = '7';
 
 const COUNT_IS_PATH: u8 = 9;
 
+type Path = Vec<((usize, usize), CameFrom)>;
+
+#[derive(Debug)]
+struct PipeLoop {
+    path: Path,
+    is_clockwise: bool,
+    start_pipe: char,
+}
+
+#[derive(Debug, PartialEq)]
+enum LoopError {
+    EmptyMap,
+    NoStart,
+    NoLoop,
+    AmbiguousLoop(Vec<char>),
+    // a tile was marked from some but not all of the sides facing it
+    UnexpectedCount{row: usize, col: usize, count: u8},
+    EnclosedMismatch{by_marking: usize, by_area: usize},
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum CameFrom {
     Top,
@@ -24,21 +48,32 @@ enum CameFrom {
     Right,
 }
 
-fn process_input(input: &str) -> (usize, usize) {
+fn process_input(input: &str) -> Result<(usize, usize), LoopError> {
+    let (map, pipe_loop) = parse_and_find_path(input)?;
+
+    let steps_to_farthest = pipe_loop.path.len() / 2;
+
+    let enclosed = count_enclosed(&map, &pipe_loop.path, pipe_loop.is_clockwise)?;
+    let enclosed_by_area = count_enclosed_by_area(&pipe_loop.path);
+    if enclosed != enclosed_by_area {
+        return Err(LoopError::EnclosedMismatch{by_marking: enclosed, by_area: enclosed_by_area});
+    }
+
+    Ok((steps_to_farthest, enclosed))
+}
+
+fn parse_and_find_path(input: &str) -> Result<(Vec<Vec<char>>, PipeLoop), LoopError> {
     let input = input.trim();
-    let start_position = input.find('S').unwrap();
     let map = input
         .lines()
         .map(|line| line.chars().collect::<Vec<_>>())
         .collect::<Vec<_>>();
-    if map.len() == 0 {
-        panic!("empty map");
+    if map.is_empty() {
+        return Err(LoopError::EmptyMap);
     }
-    let (path, is_clockwise) = find_path(&map, start_position);
-
-    let steps_to_farthest = path.len() / 2;
-
-    (steps_to_farthest, count_enclosed(&map, &path, is_clockwise))
+    let start_position = input.find('S').ok_or(LoopError::NoStart)?;
+    let pipe_loop = find_path(&map, start_position)?;
+    Ok((map, pipe_loop))
 }
 
 #[test]
@@ -50,7 +85,7 @@ SJ.L7
 |F--J
 LJ...
 ";
-    let (result, _) = process_input(input);
+    let (result, _) = process_input(input).unwrap();
     assert_eq!(8, result);
 
     let input = "
@@ -64,7 +99,7 @@ LJ...
 .L--J.L--J.
 ...........
 ";
-    let (_, result) = process_input(input);
+    let (_, result) = process_input(input).unwrap();
     assert_eq!(4, result);
 
     let input = "
@@ -79,11 +114,11 @@ L---JF-JLJIIIIFJLJJ7
 L.L7LFJ|||||FJL7||LJ
 L7JLJL-JLJLJL--JLJ.L
 ";
-    let (_, result) = process_input(input);
+    let (_, result) = process_input(input).unwrap();
     assert_eq!(10, result);
 }
 
-fn find_path(map: &[Vec<char>], start_position: usize) -> (Vec<((usize, usize), CameFrom)>, bool) {
+fn find_path(map: &[Vec<char>], start_position: usize) -> Result<PipeLoop, LoopError> {
     let start_position = start_position as isize;
     let cols_count = map[0].len() as isize;
     let start_row = start_position / (cols_count + 1); // + 1 because start_position had new lines, but "map" does not
@@ -94,15 +129,105 @@ fn find_path(map: &[Vec<char>], start_position: usize) -> (Vec<((usize, usize),
         ((start_row + 1, start_col), CameFrom::Top),
         ((start_row, start_col - 1), CameFrom::Right), // the last one is probably always unreachable
     ];
+    let mut loops: Vec<PipeLoop> = vec![];
     for ((start_check_row, start_check_col), start_came_from) in checks {
-        if let Some(result) = trace_path(&map, start_check_row, start_check_col, start_came_from) {
-            return result;
+        let Some((path, is_clockwise)) = trace_path(map, start_check_row, start_check_col, start_came_from) else {
+            continue;
+        };
+        // each loop is found twice, once for each direction
+        let start_pipe = get_start_pipe(start_came_from, path.last().unwrap().1);
+        if loops.iter().all(|x| x.start_pipe != start_pipe) {
+            loops.push(PipeLoop{path, is_clockwise, start_pipe});
         }
     }
-    panic!("no loops found?");
+    match loops.len() {
+        0 => Err(LoopError::NoLoop),
+        1 => Ok(loops.pop().unwrap()),
+        _ => Err(LoopError::AmbiguousLoop(loops.iter().map(|x| x.start_pipe).collect())),
+    }
+}
+
+fn get_start_pipe(start_came_from: CameFrom, end_came_from: CameFrom) -> char {
+    // we leave S through the side that is opposite to where the next tile was entered from
+    let exit_side = match start_came_from {
+        CameFrom::Top => CameFrom::Bottom,
+        CameFrom::Bottom => CameFrom::Top,
+        CameFrom::Left => CameFrom::Right,
+        CameFrom::Right => CameFrom::Left,
+    };
+    match (exit_side, end_came_from) {
+        (CameFrom::Top, CameFrom::Bottom) | (CameFrom::Bottom, CameFrom::Top) => TOP_BOTTOM,
+        (CameFrom::Left, CameFrom::Right) | (CameFrom::Right, CameFrom::Left) => LEFT_RIGHT,
+        (CameFrom::Top, CameFrom::Right) | (CameFrom::Right, CameFrom::Top) => TOP_RIGHT,
+        (CameFrom::Top, CameFrom::Left) | (CameFrom::Left, CameFrom::Top) => TOP_LEFT,
+        (CameFrom::Bottom, CameFrom::Right) | (CameFrom::Right, CameFrom::Bottom) => BOTTOM_RIGHT,
+        (CameFrom::Bottom, CameFrom::Left) | (CameFrom::Left, CameFrom::Bottom) => BOTTOM_LEFT,
+        _ => panic!("loop leaves and enters S from the same side {:?}", exit_side),
+    }
 }
 
-fn trace_path(map: &[Vec<char>], start_check_row: isize, start_check_col: isize, start_came_from: CameFrom) -> Option<(Vec<((usize, usize), CameFrom)>, bool)> {
+#[test]
+fn test_find_path() {
+    let input = "
+..F7.
+.FJ|.
+SJ.L7
+|F--J
+LJ...
+";
+    let (_, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(BOTTOM_RIGHT, pipe_loop.start_pipe);
+    assert_eq!(16, pipe_loop.path.len());
+
+    let input = "
+.....
+.S-7.
+.|.|.
+.L-J.
+.....
+";
+    let (_, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(BOTTOM_RIGHT, pipe_loop.start_pipe);
+    let input = "
+.....
+.F-7.
+.|.|.
+.S-J.
+.....
+";
+    let (_, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(TOP_RIGHT, pipe_loop.start_pipe);
+    let input = "
+.....
+.F-7.
+.S.|.
+.L-J.
+.....
+";
+    let (_, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(TOP_BOTTOM, pipe_loop.start_pipe);
+
+    let input = "
+F-7..
+|.|..
+L-S-7
+..|.|
+..L-J
+";
+    let error = parse_and_find_path(input).unwrap_err();
+    assert_eq!(LoopError::AmbiguousLoop(vec![TOP_LEFT, BOTTOM_RIGHT]), error);
+
+    let input = "
+.S-7.
+.|.|.
+.L-..
+";
+    assert_eq!(LoopError::NoLoop, parse_and_find_path(input).unwrap_err());
+    assert_eq!(LoopError::NoStart, parse_and_find_path(".F7.").unwrap_err());
+    assert_eq!(LoopError::EmptyMap, parse_and_find_path("\n").unwrap_err());
+}
+
+fn trace_path(map: &[Vec<char>], start_check_row: isize, start_check_col: isize, start_came_from: CameFrom) -> Option<(Path, bool)> {
     let mut check_row = start_check_row;
     let mut check_col = start_check_col;
     let mut came_from = start_came_from;
@@ -150,7 +275,21 @@ fn trace_path(map: &[Vec<char>], start_check_row: isize, start_check_col: isize,
     Some((path, rotation > 0))
 }
 
-fn count_enclosed(map: &[Vec<char>], path: &[((usize, usize), CameFrom)], is_clockwise: bool) -> usize {
+fn count_enclosed(map: &[Vec<char>], path: &[((usize, usize), CameFrom)], is_clockwise: bool) -> Result<usize, LoopError> {
+    let count = mark_enclosed(map, path, is_clockwise)?
+        .iter()
+        .map(
+            |row|
+            row
+                .iter()
+                .filter(|&&x| x == 4)
+                .count()
+        )
+        .sum();
+    Ok(count)
+}
+
+fn mark_enclosed(map: &[Vec<char>], path: &[((usize, usize), CameFrom)], is_clockwise: bool) -> Result<Vec<Vec<u8>>, LoopError> {
     let rows_count = map.len();
     let cols_count = map[0].len();
     let mut counts_map = vec![vec![0u8; cols_count]; rows_count];
@@ -160,28 +299,132 @@ fn count_enclosed(map: &[Vec<char>], path: &[((usize, usize), CameFrom)], is_clo
     let shift = if is_clockwise { 1 } else { -1 };
     for (index, &((path_row, path_col), came_from)) in path.iter().enumerate() {
         mark_as_internal(&mut counts_map, path_row, path_col, came_from, shift);
-        let next_step = path.get(index + 1).or(Some(&path[0])).unwrap();
+        let next_step = path.get(index + 1).unwrap_or(&path[0]);
         if next_step.1 != came_from {
             // correctly process bends
             mark_as_internal(&mut counts_map, path_row, path_col, next_step.1, shift);
         }
     }
-    if counts_map.iter().find(|row| row.iter().find(|&&x| (x != 0) && (x != 4) && (x != COUNT_IS_PATH)).is_some()).is_some() {
-        for row in counts_map.iter() {
-            println!("{:?}", row);
+    for (row, line) in counts_map.iter().enumerate() {
+        if let Some(col) = line.iter().position(|&x| (x != 0) && (x != 4) && (x != COUNT_IS_PATH)) {
+            return Err(LoopError::UnexpectedCount{row, col, count: line[col]});
         }
-        panic!("found unexpected count");
     }
-    counts_map
-        .iter()
-        .map(
-            |row|
-            row
-                .iter()
-                .filter(|&&x| x == 4)
-                .count()
-        )
-        .sum()
+    Ok(counts_map)
+}
+
+#[test]
+fn test_mark_enclosed_wrong_side() {
+    let input = "
+.....
+.S-7.
+.|.|.
+.L-J.
+.....
+";
+    let (map, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert!(mark_enclosed(&map, &pipe_loop.path, pipe_loop.is_clockwise).is_ok());
+    // marking the other side of the loop runs into the outside
+    let error = mark_enclosed(&map, &pipe_loop.path, !pipe_loop.is_clockwise).unwrap_err();
+    assert!(matches!(error, LoopError::UnexpectedCount{..}), "{:?}", error);
+}
+
+// shoelace formula gives the area of the polygon through the tile centers,
+// and Pick's theorem turns it into the number of tiles strictly inside
+fn count_enclosed_by_area(path: &[((usize, usize), CameFrom)]) -> usize {
+    let mut double_area = 0isize;
+    for (index, &((row, col), _)) in path.iter().enumerate() {
+        let ((next_row, next_col), _) = path[(index + 1) % path.len()];
+        double_area += (col * next_row) as isize - (next_col * row) as isize;
+    }
+    let area = double_area.unsigned_abs() / 2;
+    area + 1 - path.len() / 2
+}
+
+#[test]
+fn test_count_enclosed_by_area() {
+    let input = "
+.....
+.S-7.
+.|.|.
+.L-J.
+.....
+";
+    let (_, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(1, count_enclosed_by_area(&pipe_loop.path));
+
+    let input = "
+..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........
+";
+    let (map, pipe_loop) = parse_and_find_path(input).unwrap();
+    assert_eq!(4, count_enclosed_by_area(&pipe_loop.path));
+    assert_eq!(Ok(4), count_enclosed(&map, &pipe_loop.path, pipe_loop.is_clockwise));
+}
+
+fn render_input(input: &str) -> Result<String, LoopError> {
+    let (map, pipe_loop) = parse_and_find_path(input)?;
+    render_loop(&map, &pipe_loop)
+}
+
+fn render_loop(map: &[Vec<char>], pipe_loop: &PipeLoop) -> Result<String, LoopError> {
+    let counts_map = mark_enclosed(map, &pipe_loop.path, pipe_loop.is_clockwise)?;
+    let mut result = String::new();
+    for (row, line) in map.iter().enumerate() {
+        for (col, &char) in line.iter().enumerate() {
+            let char = if char == 'S' { pipe_loop.start_pipe } else { char };
+            let rendered = match counts_map[row][col] {
+                COUNT_IS_PATH => match char {
+                    TOP_BOTTOM => '│',
+                    LEFT_RIGHT => '─',
+                    TOP_RIGHT => '└',
+                    TOP_LEFT => '┘',
+                    BOTTOM_RIGHT => '┌',
+                    BOTTOM_LEFT => '┐',
+                    _ => char,
+                },
+                0 => 'O',
+                _ => 'I',
+            };
+            result.push(rendered);
+        }
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[test]
+fn test_render_loop() {
+    let input = "
+...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........
+";
+    let expected = "
+OOOOOOOOOOO
+O┌───────┐O
+O│┌─────┐│O
+O││OOOOO││O
+O││OOOOO││O
+O│└─┐O┌─┘│O
+O│II│O│II│O
+O└──┘O└──┘O
+OOOOOOOOOOO
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_input(input).unwrap());
 }
 
 fn mark_as_internal(counts_map: &mut [Vec<u8>], path_row: usize, path_col: usize, came_from: CameFrom, shift: isize) {
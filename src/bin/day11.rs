@@ -1,5 +1,4 @@
 use std::fs::read_to_string;
-use std::cmp;
 
 fn main() {
     let file_contents = read_to_string("inputs/day11.txt").unwrap();
@@ -10,56 +9,120 @@ fn main() {
 }
 
 fn process_input(input: &str, expand_factor: usize) -> usize {
-    let input = input.trim();
-    let galaxy_indexes = input
-        .chars()
-        .enumerate()
-        .filter(|&(_, x)| x == '#')
-        .map(|(i, _)| i)
-        .collect::<Vec<_>>();
+    let galaxies = expand_galaxies(input, expand_factor);
+    let rows = galaxies.iter().map(|x| x.0).collect::<Vec<_>>();
+    let cols = galaxies.iter().map(|x| x.1).collect::<Vec<_>>();
+    sum_axis_distances(rows) + sum_axis_distances(cols)
+}
+
+#[test]
+fn test_process_input() {
+    let input = "
+...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....
+";
+    let result = process_input(input, 2);
+    assert_eq!(374, result);
+    let result = process_input(input, 10);
+    assert_eq!(1030, result);
+    let result = process_input(input, 100);
+    assert_eq!(8410, result);
+}
+
+fn expand_galaxies(input: &str, expand_factor: usize) -> Vec<(usize, usize)> {
     let map = input
+        .trim()
         .lines()
         .map(|x| x.chars().collect::<Vec<_>>())
         .collect::<Vec<_>>();
     let cols_count = map[0].len();
-    let orig_cols_count = cols_count + 1;
-    let galaxy_indexes = galaxy_indexes
-        .into_iter()
-        .map(|x| (x / orig_cols_count, x % orig_cols_count))
-        .collect::<Vec<_>>();
     let rows_is_empty = map
         .iter()
-        .map(|row| row.iter().all(|&x| x == '.') as u8)
+        .map(|row| row.iter().all(|&x| x == '.'))
         .collect::<Vec<_>>();
     let cols_is_empty = (0..cols_count)
-        .map(|i| map.iter().all(|row| row[i] == '.') as u8)
+        .map(|i| map.iter().all(|row| row[i] == '.'))
         .collect::<Vec<_>>();
 
-    let expand_factor = expand_factor - 1; // they are already counted once in the _normal counts
-
-    let mut dist_sum = 0;
-    for (first_index, &(first_row, first_col)) in galaxy_indexes.iter().enumerate() {
-        for &(second_row, second_col) in galaxy_indexes.iter().skip(first_index + 1) {
-            let row_from = cmp::min(first_row, second_row);
-            let row_to = cmp::max(first_row, second_row);
-            let rows_diff_normal = row_to - row_from;
-            let empty_rows_between = rows_is_empty[row_from..row_to].iter().sum::<u8>() as usize;
+    let expand_factor = expand_factor - 1; // an empty line is already counted once by the plain coordinate, offsets only add the extra copies
+    let row_offsets = get_expand_offsets(&rows_is_empty, expand_factor);
+    let col_offsets = get_expand_offsets(&cols_is_empty, expand_factor);
 
-            let col_from = cmp::min(first_col, second_col);
-            let col_to = cmp::max(first_col, second_col);
-            let cols_diff_normal = col_to - col_from;
-            let empty_cols_between = cols_is_empty[col_from..col_to].iter().sum::<u8>() as usize;
+    let mut galaxies = vec![];
+    for (row, line) in map.iter().enumerate() {
+        for (col, &char) in line.iter().enumerate() {
+            if char == '#' {
+                galaxies.push((row + row_offsets[row], col + col_offsets[col]));
+            }
+        }
+    }
+    galaxies
+}
 
-            let distance = rows_diff_normal + cols_diff_normal + ((empty_rows_between + empty_cols_between) * expand_factor);
-            dist_sum += distance;
+fn get_expand_offsets(is_empty: &[bool], expand_factor: usize) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(is_empty.len());
+    let mut offset = 0;
+    for &is_empty in is_empty {
+        offsets.push(offset);
+        if is_empty {
+            offset += expand_factor;
         }
     }
+    offsets
+}
+
+#[test]
+fn test_expand_galaxies() {
+    let input = "
+#..
+...
+..#
+";
+    assert_eq!([(0, 0), (3, 3)], expand_galaxies(input, 2)[..]);
+    assert_eq!([(0, 0), (11, 11)], expand_galaxies(input, 10)[..]);
+}
 
+fn sum_axis_distances(mut coords: Vec<usize>) -> usize {
+    coords.sort();
+    let mut prefix_sum = 0;
+    let mut dist_sum = 0;
+    for (index, coord) in coords.into_iter().enumerate() {
+        dist_sum += coord * index - prefix_sum;
+        prefix_sum += coord;
+    }
     dist_sum
 }
 
 #[test]
-fn test_process_input() {
+fn test_sum_axis_distances() {
+    assert_eq!(0, sum_axis_distances(vec![]));
+    assert_eq!(0, sum_axis_distances(vec![5]));
+    assert_eq!(4, sum_axis_distances(vec![3, 1, 3]));
+    assert_eq!(30, sum_axis_distances(vec![10, 0, 5, 5]));
+}
+
+fn galaxy_distance(first: (usize, usize), second: (usize, usize)) -> usize {
+    first.0.abs_diff(second.0) + first.1.abs_diff(second.1)
+}
+
+#[allow(dead_code)]
+fn distance_matrix(galaxies: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    galaxies
+        .iter()
+        .map(|&first| galaxies.iter().map(|&second| galaxy_distance(first, second)).collect())
+        .collect()
+}
+
+#[test]
+fn test_galaxy_distance() {
     let input = "
 ...#......
 .......#..
@@ -72,10 +135,16 @@ fn test_process_input() {
 .......#..
 #...#.....
 ";
-    let result = process_input(input, 2);
-    assert_eq!(374, result);
-    let result = process_input(input, 10);
-    assert_eq!(1030, result);
-    let result = process_input(input, 100);
-    assert_eq!(8410, result);
+    let galaxies = expand_galaxies(input, 2);
+    assert_eq!(9, galaxy_distance(galaxies[4], galaxies[8]));
+    assert_eq!(15, galaxy_distance(galaxies[0], galaxies[6]));
+    assert_eq!(17, galaxy_distance(galaxies[2], galaxies[5]));
+    assert_eq!(5, galaxy_distance(galaxies[7], galaxies[8]));
+
+    let matrix = distance_matrix(&galaxies);
+    assert_eq!(9, matrix.len());
+    assert_eq!(0, matrix[3][3]);
+    assert_eq!(matrix[4][8], matrix[8][4]);
+    let total = matrix.iter().flatten().sum::<usize>() / 2;
+    assert_eq!(374, total);
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::env;
 use std::fs::read_to_string;
 
 fn main() {
@@ -7,32 +8,29 @@ fn main() {
     println!("{}", result);
     let result = process_input_pt2(&file_contents, 1000000000);
     println!("{}", result);
-}
 
-fn process_input_pt1(input: &str) -> usize {
-    let lines = split_str(input);
-    let cols_count = lines[0].len();
-    let rows_count = lines.len();
-    let mut total_weight = 0;
-    for col in 0..cols_count {
-        let mut current_chain_size = 0;
-        for row in (0..rows_count).rev() {
-            let char = lines[row][col];
-            match char {
-                '#' => {
-                    total_weight += calc_chain_weight(current_chain_size, row + 1, rows_count);
-                    current_chain_size = 0;
-                },
-                'O' => {
-                    current_chain_size += 1;
-                },
-                '.' => {},
-                _ => panic!("unexpected char {}", char),
-            }
+    let platform = Platform::parse(&file_contents);
+    if env::args().any(|x| x == "--loads") {
+        let spin_cycle = find_spin_cycle(&platform);
+        println!("prefix {}, period {}", spin_cycle.prefix, spin_cycle.period);
+        for (cycles, loads) in spin_cycle.get_loads(&platform).iter().enumerate() {
+            println!(
+                "{}: north {}, west {}, south {}, east {}",
+                cycles, loads.north, loads.west, loads.south, loads.east,
+            );
         }
-        total_weight += calc_chain_weight(current_chain_size, 0, rows_count);
     }
-    total_weight
+    if let Some(cycles) = env::args().find_map(|x| x.strip_prefix("--cycle=").map(String::from)) {
+        let cycles = cycles.parse::<usize>().unwrap_or_else(|_| panic!("unexpected cycle number {}", cycles));
+        let spin_cycle = find_spin_cycle(&platform);
+        println!("{}", spin_cycle.platform_after(&platform, cycles).render());
+    }
+}
+
+fn process_input_pt1(input: &str) -> usize {
+    let mut platform = Platform::parse(input);
+    platform.tilt(Direction::North);
+    platform.calc_load(Direction::North)
 }
 
 fn split_str(input: &str) -> Vec<Vec<char>> {
@@ -43,15 +41,6 @@ fn split_str(input: &str) -> Vec<Vec<char>> {
         .collect::<Vec<_>>()
 }
 
-fn calc_chain_weight(chain_size: usize, last_chain_row: usize, rows_count: usize) -> usize {
-    if chain_size == 0 {
-        return 0;
-    }
-    let last_stone_weight = rows_count - last_chain_row;
-    // n + n-1 + n-2 ... n-k
-    (last_stone_weight * chain_size) - (chain_size * (chain_size - 1) / 2)
-}
-
 #[test]
 fn test_process_input() {
     let input = "
@@ -74,125 +63,285 @@ O.#..O.#.#
 }
 
 fn process_input_pt2(input: &str, cycles: usize) -> usize {
-    let mut lines = split_str(input);
+    let platform = Platform::parse(input);
+    let spin_cycle = find_spin_cycle(&platform);
+    spin_cycle.platform_after(&platform, cycles).calc_load(Direction::North)
+}
+
+// platform states after 0, 1, 2... spin cycles, where states from `prefix` on repeat every `period`
+struct SpinCycle {
+    states: Vec<Vec<u128>>,
+    prefix: usize,
+    period: usize,
+}
+
+impl SpinCycle {
+    fn state_index(&self, cycles: usize) -> usize {
+        if cycles < self.prefix {
+            cycles
+        } else {
+            self.prefix + (cycles - self.prefix) % self.period
+        }
+    }
+
+    fn platform_after(&self, platform: &Platform, cycles: usize) -> Platform {
+        Platform{rocks: self.states[self.state_index(cycles)].clone(), ..platform.clone()}
+    }
+
+    // loads after every spin cycle up to the end of the first period
+    fn get_loads(&self, platform: &Platform) -> Vec<BeamLoads> {
+        (0..self.states.len())
+            .map(|cycles| self.platform_after(platform, cycles).calc_loads())
+            .collect()
+    }
+}
 
-    let mut cache = HashMap::new();
-    let mut cycle_first = 0;
-    let mut cycle_last = 0;
-    for iteration in 0..cycles {
+fn find_spin_cycle(platform: &Platform) -> SpinCycle {
+    let mut platform = platform.clone();
+    let mut seen = HashMap::new();
+    let mut states = vec![];
+    for iteration in 0.. {
         if iteration > 10000 {
             panic!("did not find cycle after {} iterations", iteration);
         }
-        let state = map_to_string(&lines);
-        if let Some(&same_iter) = cache.get(&state) {
-            cycle_first = same_iter;
-            cycle_last = iteration;
-            break;
+        if let Some(&prefix) = seen.get(&platform.rocks) {
+            return SpinCycle{states, prefix, period: iteration - prefix};
         }
-        cache.insert(state, iteration);
+        seen.insert(platform.rocks.clone(), iteration);
+        states.push(platform.rocks.clone());
 
-        do_cycle(&mut lines);
+        platform.do_cycle();
     }
-    let cycle_last_result = if cycle_last == 0 {
-        // we have exited the loop because cycles is too small
-        lines
-    } else {
-        let cycle_len = cycle_last - cycle_first;
-        let last_cycle_index = ((cycles - cycle_first) % cycle_len) + cycle_first;
-        let cycle_last_result = cache
-            .iter()
-            .find(|&(_, val)| *val == last_cycle_index)
-            .map(|(key, _)| key)
-            .unwrap();
-        split_str(cycle_last_result)
-    };
-    calc_current_load(&cycle_last_result)
+    unreachable!()
 }
 
-fn do_cycle(map: &mut[Vec<char>]) {
-    let rows_count = map.len();
-    let cols_count = map[0].len();
-
-    for col in 0..cols_count {
-        let mut available_row = 0;
-        for row in 0..rows_count {
-            let char = map[row][col];
-            match char {
-                '#' => available_row = row + 1,
-                'O' => {
-                    if available_row != row {
-                        map[available_row][col] = char;
-                        map[row][col] = '.'
-                    }
-                    available_row += 1;
-                },
-                '.' => {},
-                _ => panic!("unexpected char {}", char),
+#[test]
+fn test_find_spin_cycle() {
+    let input = "
+O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....
+";
+    let platform = Platform::parse(input);
+    let spin_cycle = find_spin_cycle(&platform);
+    assert_eq!(3, spin_cycle.prefix);
+    assert_eq!(7, spin_cycle.period);
+    assert_eq!(10, spin_cycle.states.len());
+    assert_eq!(5, spin_cycle.state_index(5));
+    assert_eq!(3, spin_cycle.state_index(10));
+    assert_eq!(6, spin_cycle.state_index(1000000000));
+
+    let loads = spin_cycle.get_loads(&platform);
+    assert_eq!(vec![104, 87, 69, 69, 69, 65, 64, 65, 63, 68], loads.iter().map(|x| x.north).collect::<Vec<_>>());
+    assert_eq!(BeamLoads{north: 104, west: 121, south: 94, east: 77}, loads[0]);
+
+    let mut expected = platform.clone();
+    for _ in 0..25 {
+        expected.do_cycle();
+    }
+    assert_eq!(expected, spin_cycle.platform_after(&platform, 25));
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct BeamLoads {
+    north: usize,
+    west: usize,
+    south: usize,
+    east: usize,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+/*
+Each row is a bitboard with bit `col` set for a rock in that column. Tilting moves every rock
+that has a free cell next to it one step at a time, a whole row (north/south) or a whole
+bitboard word (west/east) at once, until nothing moves.
+*/
+#[derive(Debug, Clone, PartialEq)]
+struct Platform {
+    cols_count: usize,
+    rocks: Vec<u128>,
+    walls: Vec<u128>,
+}
+
+impl Platform {
+    fn parse(input: &str) -> Self {
+        let lines = split_str(input);
+        let cols_count = lines[0].len();
+        if cols_count > 128 {
+            panic!("unexpected platform width {}", cols_count);
+        }
+        let mut rocks = vec![0; lines.len()];
+        let mut walls = vec![0; lines.len()];
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != cols_count {
+                panic!("unexpected line length {} on row {}", line.len(), row);
+            }
+            for (col, &char) in line.iter().enumerate() {
+                match char {
+                    'O' => rocks[row] |= 1 << col,
+                    '#' => walls[row] |= 1 << col,
+                    '.' => {},
+                    _ => panic!("unexpected char {}", char),
+                }
             }
         }
+        Self{cols_count, rocks, walls}
     }
 
-    for row in 0..rows_count {
-        let mut available_col = 0;
-        for col in 0..cols_count {
-            let char = map[row][col];
-            match char {
-                '#' => available_col = col + 1,
-                'O' => {
-                    if available_col != col {
-                        map[row][available_col] = char;
-                        map[row][col] = '.'
-                    }
-                    available_col += 1;
-                },
-                '.' => {},
-                _ => panic!("unexpected char {}", char),
+    fn row_mask(&self) -> u128 {
+        u128::MAX >> (128 - self.cols_count)
+    }
+
+    fn tilt(&mut self, direction: Direction) {
+        match direction {
+            Direction::North => self.tilt_rows(|row| row.checked_sub(1)),
+            Direction::South => {
+                let rows_count = self.rocks.len();
+                self.tilt_rows(|row| Some(row + 1).filter(|&x| x < rows_count))
+            },
+            Direction::West => self.tilt_cols(|x| x >> 1, |x| x << 1),
+            Direction::East => self.tilt_cols(|x| x << 1, |x| x >> 1),
+        }
+    }
+
+    fn tilt_rows(&mut self, next_row: impl Fn(usize) -> Option<usize>) {
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for row in 0..self.rocks.len() {
+                let Some(next) = next_row(row) else { continue };
+                let movable = self.rocks[row] & !(self.rocks[next] | self.walls[next]);
+                if movable != 0 {
+                    self.rocks[row] &= !movable;
+                    self.rocks[next] |= movable;
+                    moved = true;
+                }
             }
         }
     }
 
-    for col in 0..cols_count {
-        let mut available_row = rows_count - 1;
-        for row in (0..rows_count).rev() {
-            let char = map[row][col];
-            match char {
-                '#' => if row > 0 { available_row = row - 1 },
-                'O' => {
-                    if available_row != row {
-                        map[available_row][col] = char;
-                        map[row][col] = '.'
-                    }
-                    if available_row > 0 {
-                        available_row -= 1;
-                    }
-                },
-                '.' => {},
-                _ => panic!("unexpected char {}", char),
+    // `step` moves bits in the tilt direction, `back` moves them the opposite way
+    fn tilt_cols(&mut self, step: impl Fn(u128) -> u128, back: impl Fn(u128) -> u128) {
+        let mask = self.row_mask();
+        for row in 0..self.rocks.len() {
+            let walls = self.walls[row];
+            let mut rocks = self.rocks[row];
+            loop {
+                let free = !(rocks | walls) & mask;
+                let movable = rocks & back(free);
+                if movable == 0 {
+                    break;
+                }
+                rocks = (rocks & !movable) | step(movable);
             }
+            self.rocks[row] = rocks;
         }
     }
 
+    fn calc_loads(&self) -> BeamLoads {
+        BeamLoads{
+            north: self.calc_load(Direction::North),
+            west: self.calc_load(Direction::West),
+            south: self.calc_load(Direction::South),
+            east: self.calc_load(Direction::East),
+        }
+    }
+
+    fn do_cycle(&mut self) {
+        for direction in [Direction::North, Direction::West, Direction::South, Direction::East] {
+            self.tilt(direction);
+        }
+    }
 
-    for row in 0..rows_count {
-        let mut available_col = cols_count - 1;
-        for col in (0..cols_count).rev() {
-            let char = map[row][col];
-            match char {
-                '#' => if col > 0 { available_col = col - 1 },
-                'O' => {
-                    if available_col != col {
-                        map[row][available_col] = char;
-                        map[row][col] = '.'
+    // load on the beams supporting the given side of the platform
+    fn calc_load(&self, direction: Direction) -> usize {
+        let rows_count = self.rocks.len();
+        match direction {
+            Direction::North | Direction::South => self.rocks
+                .iter()
+                .enumerate()
+                .map(
+                    |(row, &rocks)| {
+                        let distance = if direction == Direction::North { rows_count - row } else { row + 1 };
+                        rocks.count_ones() as usize * distance
                     }
-                    if available_col > 0 {
-                        available_col -= 1;
+                )
+                .sum(),
+            Direction::West | Direction::East => (0..self.cols_count)
+                .map(
+                    |col| {
+                        let distance = if direction == Direction::West { self.cols_count - col } else { col + 1 };
+                        self.rocks.iter().filter(|&&x| x & (1 << col) != 0).count() * distance
                     }
-                },
-                '.' => {},
-                _ => panic!("unexpected char {}", char),
-            }
+                )
+                .sum(),
         }
     }
+
+    fn render(&self) -> String {
+        self.rocks
+            .iter()
+            .zip(self.walls.iter())
+            .map(
+                |(&rocks, &walls)|
+                    (0..self.cols_count)
+                        .map(
+                            |col|
+                                if rocks & (1 << col) != 0 {
+                                    'O'
+                                } else if walls & (1 << col) != 0 {
+                                    '#'
+                                } else {
+                                    '.'
+                                }
+                        )
+                        .collect::<String>()
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[test]
+fn test_tilt() {
+    let input = "
+O.#O.
+.O..O
+#.O.O
+";
+    let platform = Platform::parse(input);
+    let cases = [
+        (Direction::North, "OO#OO\n..O.O\n#...."),
+        (Direction::South, "..#..\nO...O\n#OOOO"),
+        (Direction::West, "O.#O.\nOO...\n#OO.."),
+        (Direction::East, ".O#.O\n...OO\n#..OO"),
+    ];
+    for (direction, expected) in cases {
+        let mut tilted = platform.clone();
+        tilted.tilt(direction);
+        assert_eq!(expected, tilted.render());
+    }
+
+    let wide = format!("\nO{}\n#{}O\n", ".".repeat(127), ".".repeat(126));
+    let mut platform = Platform::parse(&wide);
+    platform.tilt(Direction::East);
+    assert_eq!(format!("{}O\n#{}O", ".".repeat(127), ".".repeat(126)), platform.render());
+    platform.tilt(Direction::West);
+    assert_eq!(format!("O{}\n#O{}", ".".repeat(127), ".".repeat(126)), platform.render());
 }
 
 #[test]
@@ -209,9 +358,9 @@ O.#..O.#.#
 #....###..
 #OO..#....
 ";
-    let mut lines = split_str(input);
+    let mut platform = Platform::parse(input);
 
-    do_cycle(&mut lines);
+    platform.do_cycle();
     let expected = "
 .....#....
 ....#...O#
@@ -225,9 +374,9 @@ O.#..O.#.#
 #..OO#....
 ";
     let expected = expected.trim();
-    assert_eq!(expected, map_to_string(&lines).as_str());
+    assert_eq!(expected, platform.render());
 
-    do_cycle(&mut lines);
+    platform.do_cycle();
     let expected = "
 .....#....
 ....#...O#
@@ -241,9 +390,9 @@ O.#..O.#.#
 #.OOO#...O
 ";
     let expected = expected.trim();
-    assert_eq!(expected, map_to_string(&lines).as_str());
+    assert_eq!(expected, platform.render());
 
-    do_cycle(&mut lines);
+    platform.do_cycle();
     let expected = "
 .....#....
 ....#...O#
@@ -257,27 +406,5 @@ O.#..O.#.#
 #.OOO#...O
 ";
     let expected = expected.trim();
-    assert_eq!(expected, map_to_string(&lines).as_str());
-}
-
-fn calc_current_load(map: &[Vec<char>]) -> usize {
-    let cols_count = map[0].len();
-    let rows_count = map.len();
-    let mut total_weight = 0;
-    for row in 0..rows_count {
-        for col in 0..cols_count {
-            if map[row][col] == 'O' {
-                total_weight += rows_count - row;
-            }
-        }
-    }
-    total_weight
-}
-
-fn map_to_string(map: &[Vec<char>]) -> String {
-    map
-        .iter()
-        .map(|x| x.iter().collect::<String>())
-        .collect::<Vec<_>>()
-        .join("\n")
+    assert_eq!(expected, platform.render());
 }
@@ -1,14 +1,36 @@
+use std::collections::HashMap;
+use std::env;
 use std::fs::read_to_string;
+#[cfg(test)]
+use rust_aoc_2023::random::XorShift;
 
 fn main() {
     let file_contents = read_to_string("inputs/day16.txt").unwrap();
-    let result = process_input_pt1(&file_contents);
+    let registry = if env::args().any(|x| x == "--extended") {
+        ElementRegistry::extended()
+    } else {
+        ElementRegistry::standard()
+    };
+    let result = process_input_pt1(&file_contents, &registry);
     println!("{}", result);
-    let result = process_input_pt2(&file_contents);
+    let result = process_input_pt2(&file_contents, &registry);
     println!("{}", result);
+
+    let entry = env::args().find_map(|x| x.strip_prefix("--entry=").map(String::from));
+    if env::args().any(|x| x == "--render") || entry.is_some() {
+        let map = parse_input(&file_contents);
+        let start_from = match entry {
+            Some(entry) => parse_entry_point(&entry, &map),
+            None => (0, 0, CameFrom::Left),
+        };
+        let beams = trace_beams(&map, start_from, &registry);
+        println!("{}", render_beams(&map, &beams));
+        println!("{}", render_energized(&beams));
+        println!("{}", BeamGraph::new(&map, &registry).count_energized(start_from));
+    }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 enum CameFrom {
     Left,
     Top,
@@ -16,31 +38,63 @@ enum CameFrom {
     Bottom,
 }
 
-fn process_input_pt1(input: &str) -> usize {
+type Beam = (isize, isize, CameFrom);
+
+fn process_input_pt1(input: &str, registry: &ElementRegistry) -> usize {
     let map = parse_input(input);
-    count_energized(&map, (0, 0, CameFrom::Left))
+    BeamGraph::new(&map, registry).count_energized((0, 0, CameFrom::Left))
 }
 
-fn process_input_pt2(input: &str) -> usize {
+fn process_input_pt2(input: &str, registry: &ElementRegistry) -> usize {
     let map = parse_input(input);
+    BeamGraph::new(&map, registry)
+        .count_energized_all(&get_entry_points(&map))
+        .into_iter()
+        .max()
+        .unwrap()
+}
 
-    let mut attempts = vec![];
+fn get_entry_points(map: &[Vec<char>]) -> Vec<Beam> {
+    let mut entry_points = vec![];
     let last_row = (map.len() - 1) as isize;
     let last_col = (map[0].len() - 1) as isize;
     for row in 0..=last_row {
-        attempts.push((row, 0, CameFrom::Left));
-        attempts.push((row, last_col, CameFrom::Right));
+        entry_points.push((row, 0, CameFrom::Left));
+        entry_points.push((row, last_col, CameFrom::Right));
     }
     for col in 0..=last_col {
-        attempts.push((0, col, CameFrom::Top));
-        attempts.push((last_row, col, CameFrom::Bottom));
+        entry_points.push((0, col, CameFrom::Top));
+        entry_points.push((last_row, col, CameFrom::Bottom));
     }
+    entry_points
+}
 
-    attempts
-        .iter()
-        .map(|&start_from| count_energized(&map, start_from))
-        .max()
-        .unwrap()
+// entry points are given as the map side and the row or column, like "left:0" or "bottom:7"
+fn parse_entry_point(entry: &str, map: &[Vec<char>]) -> Beam {
+    let (side, position) = entry.split_once(':').unwrap_or_else(|| panic!("unexpected entry point {}", entry));
+    let position = position.parse::<isize>().unwrap_or_else(|_| panic!("unexpected entry position {}", position));
+    let last_row = (map.len() - 1) as isize;
+    let last_col = (map[0].len() - 1) as isize;
+    let start_from = match side {
+        "left" => (position, 0, CameFrom::Left),
+        "top" => (0, position, CameFrom::Top),
+        "right" => (position, last_col, CameFrom::Right),
+        "bottom" => (last_row, position, CameFrom::Bottom),
+        _ => panic!("unexpected entry side {}", side),
+    };
+    if (start_from.0 < 0) || (start_from.1 < 0) || (start_from.0 > last_row) || (start_from.1 > last_col) {
+        panic!("entry point {} is outside the map", entry);
+    }
+    start_from
+}
+
+#[test]
+fn test_parse_entry_point() {
+    let map = parse_input("\n...\n...\n");
+    assert_eq!((1, 0, CameFrom::Left), parse_entry_point("left:1", &map));
+    assert_eq!((0, 2, CameFrom::Top), parse_entry_point("top:2", &map));
+    assert_eq!((0, 2, CameFrom::Right), parse_entry_point("right:0", &map));
+    assert_eq!((1, 1, CameFrom::Bottom), parse_entry_point("bottom:1", &map));
 }
 
 fn parse_input(input: &str) -> Vec<Vec<char>> {
@@ -51,99 +105,644 @@ fn parse_input(input: &str) -> Vec<Vec<char>> {
         .collect::<Vec<_>>()
 }
 
-fn count_energized(map: &[Vec<char>], start_from: (isize, isize, CameFrom)) -> usize {
-    let mut map = map.iter().map(|row| row.clone()).collect::<Vec<_>>();
+// directions the beam leaves an element in, expressed as the side it enters the next tile from
+trait Element {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom>;
+}
+
+struct Empty;
+
+impl Element for Empty {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom> {
+        vec![came_from]
+    }
+}
+
+// '\' when is_backslash, '/' otherwise
+struct Mirror {
+    is_backslash: bool,
+}
+
+impl Element for Mirror {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom> {
+        let turned = match came_from {
+            CameFrom::Left => CameFrom::Top,
+            CameFrom::Top => CameFrom::Left,
+            CameFrom::Right => CameFrom::Bottom,
+            CameFrom::Bottom => CameFrom::Right,
+        };
+        vec![if self.is_backslash { turned } else { turned.opposite() }]
+    }
+}
+
+// '|' when is_vertical, '-' otherwise
+struct Splitter {
+    is_vertical: bool,
+}
+
+impl Element for Splitter {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom> {
+        let is_horizontal = (came_from == CameFrom::Left) || (came_from == CameFrom::Right);
+        match (self.is_vertical, is_horizontal) {
+            (true, true) => vec![CameFrom::Top, CameFrom::Bottom],
+            (false, false) => vec![CameFrom::Left, CameFrom::Right],
+            _ => vec![came_from],
+        }
+    }
+}
+
+struct Absorber;
+
+impl Element for Absorber {
+    fn get_outgoing(&self, _came_from: CameFrom) -> Vec<CameFrom> {
+        vec![]
+    }
+}
+
+// lets through only the beams entering from the given side, drawn as the heading of the beams it passes
+struct OneWayGate {
+    passes: CameFrom,
+}
+
+impl Element for OneWayGate {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom> {
+        if came_from == self.passes { vec![came_from] } else { vec![] }
+    }
+}
+
+// sends the beam straight on and to both sides
+struct ThreeWaySplitter;
+
+impl Element for ThreeWaySplitter {
+    fn get_outgoing(&self, came_from: CameFrom) -> Vec<CameFrom> {
+        ALL_SIDES.iter().copied().filter(|&x| x != came_from.opposite()).collect()
+    }
+}
+
+const ALL_SIDES: [CameFrom; 4] = [CameFrom::Left, CameFrom::Top, CameFrom::Right, CameFrom::Bottom];
+
+impl CameFrom {
+    fn opposite(self) -> Self {
+        match self {
+            CameFrom::Left => CameFrom::Right,
+            CameFrom::Top => CameFrom::Bottom,
+            CameFrom::Right => CameFrom::Left,
+            CameFrom::Bottom => CameFrom::Top,
+        }
+    }
+}
+
+struct ElementRegistry {
+    elements: HashMap<char, Box<dyn Element>>,
+}
+
+impl ElementRegistry {
+    fn standard() -> Self {
+        let mut registry = Self{elements: HashMap::new()};
+        registry.register('.', Box::new(Empty));
+        registry.register('\\', Box::new(Mirror{is_backslash: true}));
+        registry.register('/', Box::new(Mirror{is_backslash: false}));
+        registry.register('|', Box::new(Splitter{is_vertical: true}));
+        registry.register('-', Box::new(Splitter{is_vertical: false}));
+        registry
+    }
+
+    fn extended() -> Self {
+        let mut registry = Self::standard();
+        // none of these clash with the beam arrows or the '#' of the rendered maps
+        registry.register('X', Box::new(Absorber));
+        registry.register('E', Box::new(OneWayGate{passes: CameFrom::Left}));
+        registry.register('S', Box::new(OneWayGate{passes: CameFrom::Top}));
+        registry.register('W', Box::new(OneWayGate{passes: CameFrom::Right}));
+        registry.register('N', Box::new(OneWayGate{passes: CameFrom::Bottom}));
+        registry.register('+', Box::new(ThreeWaySplitter));
+        registry
+    }
+
+    fn register(&mut self, char: char, element: Box<dyn Element>) {
+        self.elements.insert(char, element);
+    }
+
+    fn get_outgoing(&self, char: char, came_from: CameFrom) -> Vec<CameFrom> {
+        match self.elements.get(&char) {
+            Some(element) => element.get_outgoing(came_from),
+            None => panic!("unexpected element {}", char),
+        }
+    }
+}
+
+#[test]
+fn test_element_registry() {
+    let registry = ElementRegistry::extended();
+    assert_eq!(vec![CameFrom::Top], registry.get_outgoing('\\', CameFrom::Left));
+    assert_eq!(vec![CameFrom::Bottom], registry.get_outgoing('/', CameFrom::Left));
+    assert_eq!(vec![CameFrom::Right], registry.get_outgoing('/', CameFrom::Top));
+    assert_eq!(vec![CameFrom::Top, CameFrom::Bottom], registry.get_outgoing('|', CameFrom::Right));
+    assert_eq!(vec![CameFrom::Top], registry.get_outgoing('|', CameFrom::Top));
+    assert_eq!(Vec::<CameFrom>::new(), registry.get_outgoing('X', CameFrom::Top));
+    assert_eq!(vec![CameFrom::Left], registry.get_outgoing('E', CameFrom::Left));
+    assert_eq!(Vec::<CameFrom>::new(), registry.get_outgoing('E', CameFrom::Right));
+    assert_eq!(vec![CameFrom::Bottom], registry.get_outgoing('N', CameFrom::Bottom));
+    assert_eq!(vec![CameFrom::Left, CameFrom::Top, CameFrom::Bottom], registry.get_outgoing('+', CameFrom::Left));
+
+    let mut registry = ElementRegistry::standard();
+    registry.register('x', Box::new(Absorber));
+    let input = r"
+..|..
+..x..
+.....
+";
+    let map = parse_input(input);
+    assert_eq!(4, BeamGraph::new(&map, &registry).count_energized((0, 0, CameFrom::Left)));
+}
+
+#[test]
+#[should_panic(expected = "unexpected element #")]
+fn test_element_registry_unknown() {
+    ElementRegistry::standard().get_outgoing('#', CameFrom::Left);
+}
+
+#[test]
+fn test_process_input_extended() {
+    let input = r"
+.+..X
+.....
+.E.W.
+";
+    let registry = ElementRegistry::extended();
+    let map = parse_input(input);
+    // the '+' also sends the beam down, where the 'E' gate absorbs it
+    let beams = trace_beams(&map, (0, 0, CameFrom::Left), &registry);
+    let expected = "
+>+>>X
+.v...
+.E.W.";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_beams(&map, &beams));
+    assert_eq!(7, BeamGraph::new(&map, &registry).count_energized((0, 0, CameFrom::Left)));
+    assert_eq!(7, process_input_pt2(input, &registry));
+}
+
+fn advance(row: isize, col: isize, came_from: CameFrom) -> Beam {
+    match came_from {
+        CameFrom::Left => (row, col + 1, came_from),
+        CameFrom::Top => (row + 1, col, came_from),
+        CameFrom::Right => (row, col - 1, came_from),
+        CameFrom::Bottom => (row - 1, col, came_from),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TileSet {
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    fn new(size: usize) -> Self {
+        Self{bits: vec![0; size.div_ceil(64)]}
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        for (x, y) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *x |= y;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.bits.iter().map(|x| x.count_ones() as usize).sum()
+    }
+}
+
+/*
+Beams only branch where they split, so the contraption is precomputed as a graph: every
+(tile, came_from) state that emits several beams is a node, and the straight segments it
+emits lead to the next splitting nodes. Nodes in one strongly connected component energize
+the same tiles. Only the segments are kept per node, as tile indexes, and the full tile sets
+are built per component when energized tiles are counted, sinks first, each one dropped as
+soon as everything that reads it has been done.
+*/
+struct BeamGraph<'a> {
+    map: &'a [Vec<char>],
+    registry: &'a ElementRegistry,
+    nodes: HashMap<Beam, usize>,
+    // the node tile and the tiles of the segments it emits
+    node_segments: Vec<Vec<usize>>,
+    // in reverse topological order, with the distinct components each one leads to
+    components: Vec<Vec<usize>>,
+    component_edges: Vec<Vec<usize>>,
+    node_component: Vec<usize>,
+}
+
+impl<'a> BeamGraph<'a> {
+    fn new(map: &'a [Vec<char>], registry: &'a ElementRegistry) -> Self {
+        let mut nodes = HashMap::new();
+        let mut node_beams = vec![];
+        for (row, line) in map.iter().enumerate() {
+            for (col, &char) in line.iter().enumerate() {
+                for came_from in ALL_SIDES {
+                    if registry.get_outgoing(char, came_from).len() > 1 {
+                        let beam = (row as isize, col as isize, came_from);
+                        nodes.insert(beam, node_beams.len());
+                        node_beams.push(beam);
+                    }
+                }
+            }
+        }
+        let mut graph = Self{
+            map,
+            registry,
+            nodes,
+            node_segments: vec![],
+            components: vec![],
+            component_edges: vec![],
+            node_component: vec![],
+        };
+
+        let mut edges = vec![];
+        for &(row, col, came_from) in node_beams.iter() {
+            let mut tiles = vec![graph.tile_index(row, col)];
+            let mut next_nodes = vec![];
+            for next_came_from in registry.get_outgoing(map[row as usize][col as usize], came_from) {
+                let (segment, next_node) = graph.trace_segment(advance(row, col, next_came_from));
+                tiles.extend(segment);
+                next_nodes.extend(next_node);
+            }
+            graph.node_segments.push(tiles);
+            edges.push(next_nodes);
+        }
+
+        let components = get_strongly_connected_components(&edges);
+        let mut node_component = vec![0; node_beams.len()];
+        for (component, nodes) in components.iter().enumerate() {
+            for &node in nodes {
+                node_component[node] = component;
+            }
+        }
+        let component_edges = components
+            .iter()
+            .enumerate()
+            .map(
+                |(component, nodes)| {
+                    let mut next_components = nodes
+                        .iter()
+                        .flat_map(|&node| edges[node].iter().map(|&x| node_component[x]))
+                        .filter(|&x| x != component)
+                        .collect::<Vec<_>>();
+                    next_components.sort();
+                    next_components.dedup();
+                    next_components
+                }
+            )
+            .collect();
+        graph.components = components;
+        graph.component_edges = component_edges;
+        graph.node_component = node_component;
+        graph
+    }
+
+    fn new_tile_set(&self) -> TileSet {
+        TileSet::new(self.map.len() * self.map[0].len())
+    }
+
+    fn tile_index(&self, row: isize, col: isize) -> usize {
+        row as usize * self.map[0].len() + col as usize
+    }
+
+    // follows a beam until it leaves the map, is absorbed or reaches a splitting node
+    fn trace_segment(&self, start_from: Beam) -> (Vec<usize>, Option<usize>) {
+        let rows_count = self.map.len() as isize;
+        let cols_count = self.map[0].len() as isize;
+        let mut tiles = vec![];
+        let (mut row, mut col, mut came_from) = start_from;
+        // a beam can run into a loop it never leaves, so the state is compared with a checkpoint
+        // that moves to the current state at every power of two steps (Brent's cycle detection)
+        let mut checkpoint = start_from;
+        let mut next_checkpoint_step = 1usize;
+        for step in 1.. {
+            if (row < 0) || (col < 0) || (row >= rows_count) || (col >= cols_count) {
+                return (tiles, None);
+            }
+            if (step > 1) && ((row, col, came_from) == checkpoint) {
+                break;
+            }
+            if step == next_checkpoint_step {
+                checkpoint = (row, col, came_from);
+                next_checkpoint_step *= 2;
+            }
+            tiles.push(self.tile_index(row, col));
+            let outgoing = self.registry.get_outgoing(self.map[row as usize][col as usize], came_from);
+            match outgoing[..] {
+                [] => return (tiles, None),
+                [next_came_from] => (row, col, came_from) = advance(row, col, next_came_from),
+                _ => return (tiles, Some(self.nodes[&(row, col, came_from)])),
+            }
+        }
+        (tiles, None)
+    }
+
+    fn count_energized(&self, start_from: Beam) -> usize {
+        self.count_energized_all(&[start_from])[0]
+    }
+
+    fn count_energized_all(&self, entry_points: &[Beam]) -> Vec<usize> {
+        let components_count = self.components.len();
+        let mut counts = vec![0; entry_points.len()];
+        let mut entries_by_component = vec![vec![]; components_count];
+        let mut segments = vec![];
+        for (entry, &start_from) in entry_points.iter().enumerate() {
+            let (mut segment, next_node) = self.trace_segment(start_from);
+            segment.sort();
+            segment.dedup();
+            match next_node {
+                Some(node) => entries_by_component[self.node_component[node]].push(entry),
+                None => counts[entry] = segment.len(),
+            }
+            segments.push(segment);
+        }
+
+        // only the components reachable from the entry points are needed, each one read
+        // by every entry point ending in it and every needed component leading to it
+        let mut is_needed = vec![false; components_count];
+        let mut stack = (0..components_count)
+            .filter(|&x| !entries_by_component[x].is_empty())
+            .collect::<Vec<_>>();
+        while let Some(component) = stack.pop() {
+            if !is_needed[component] {
+                is_needed[component] = true;
+                stack.extend(self.component_edges[component].iter().copied());
+            }
+        }
+        let mut readers_left = entries_by_component.iter().map(|x| x.len()).collect::<Vec<_>>();
+        for component in (0..components_count).filter(|&x| is_needed[x]) {
+            for &next_component in self.component_edges[component].iter() {
+                readers_left[next_component] += 1;
+            }
+        }
+
+        let mut component_tiles: Vec<Option<TileSet>> = vec![None; components_count];
+        for component in (0..components_count).filter(|&x| is_needed[x]) {
+            let mut tiles = self.new_tile_set();
+            for &node in self.components[component].iter() {
+                for &tile in self.node_segments[node].iter() {
+                    tiles.insert(tile);
+                }
+            }
+            for &next_component in self.component_edges[component].iter() {
+                tiles.union_with(component_tiles[next_component].as_ref().unwrap());
+                readers_left[next_component] -= 1;
+                if readers_left[next_component] == 0 {
+                    component_tiles[next_component] = None;
+                }
+            }
+            if !entries_by_component[component].is_empty() {
+                let tiles_count = tiles.len();
+                for &entry in entries_by_component[component].iter() {
+                    counts[entry] = tiles_count + segments[entry].iter().filter(|&&x| !tiles.contains(x)).count();
+                }
+            }
+            if readers_left[component] > entries_by_component[component].len() {
+                readers_left[component] -= entries_by_component[component].len();
+                component_tiles[component] = Some(tiles);
+            }
+        }
+        counts
+    }
+}
+
+// traces every beam state, returns the sides beams entered each tile from
+fn trace_beams(map: &[Vec<char>], start_from: Beam, registry: &ElementRegistry) -> Vec<Vec<Vec<CameFrom>>> {
     let rows_count = map.len() as isize;
     let cols_count = map[0].len() as isize;
+    let mut beams = vec![vec![vec![]; map[0].len()]; map.len()];
     let mut stack = vec![start_from];
-    let mut iterations = 0usize;
     while let Some((row, col, came_from)) = stack.pop() {
-        iterations += 1;
-        if iterations > 100000 {
-            panic!("too many iterations");
-        }
         if (row < 0) || (col < 0) || (row >= rows_count) || (col >= cols_count) {
             continue;
         }
-
-        let row_usize = row as usize;
-        let col_usize = col as usize;
-        let char = map[row_usize][col_usize];
-        let is_horizontal = (came_from == CameFrom::Left) || (came_from == CameFrom::Right);
-        match (char, is_horizontal) {
-            ('+', _) => continue,
-            ('=', true) => continue,
-            ('!', false) => continue,
-            _ => (),
-        }
-        match char {
-            '=' => map[row_usize][col_usize] = '+',
-            '!' => map[row_usize][col_usize] = '+',
-            '.' => map[row_usize][col_usize] = if is_horizontal { '=' } else { '!' },
-            '\\' => map[row_usize][col_usize] = 'a',
-            '/' => map[row_usize][col_usize] = 'b',
-            '|' => map[row_usize][col_usize] = 'c',
-            '-' => map[row_usize][col_usize] = 'd',
-            _ => (),
-        }
-        match char {
-            '\\' | 'a' => {
-                let next = match came_from {
-                    CameFrom::Left => (row + 1, col, CameFrom::Top),
-                    CameFrom::Top => (row, col + 1, CameFrom::Left),
-                    CameFrom::Right => (row - 1, col, CameFrom::Bottom),
-                    CameFrom::Bottom => (row, col - 1, CameFrom::Right),
-                };
-                stack.push(next);
-            },
-            '/' | 'b' => {
-                let next = match came_from {
-                    CameFrom::Left => (row - 1, col, CameFrom::Bottom),
-                    CameFrom::Top => (row, col - 1, CameFrom::Right),
-                    CameFrom::Right => (row + 1, col, CameFrom::Top),
-                    CameFrom::Bottom => (row, col + 1, CameFrom::Left),
-                };
-                stack.push(next);
-            },
-            '|' | 'c' => match came_from {
-                CameFrom::Left | CameFrom::Right => {
-                    stack.push((row + 1, col, CameFrom::Top));
-                    stack.push((row - 1, col, CameFrom::Bottom));
-                },
-                CameFrom::Top => stack.push((row + 1, col, came_from)),
-                CameFrom::Bottom => stack.push((row - 1, col, came_from)),
-            },
-            '-' | 'd' => match came_from {
-                CameFrom::Top | CameFrom::Bottom => {
-                    stack.push((row, col + 1, CameFrom::Left));
-                    stack.push((row, col - 1, CameFrom::Right));
-                },
-                CameFrom::Left => stack.push((row, col + 1, came_from)),
-                CameFrom::Right => stack.push((row, col - 1, came_from)),
-            },
-            _ => {
-                let next = match came_from {
-                    CameFrom::Left => (row, col + 1, came_from),
-                    CameFrom::Top => (row + 1, col, came_from),
-                    CameFrom::Right => (row, col - 1, came_from),
-                    CameFrom::Bottom => (row - 1, col, came_from),
-                };
-                stack.push(next);
-            }
+        let tile: &mut Vec<CameFrom> = &mut beams[row as usize][col as usize];
+        if tile.contains(&came_from) {
+            continue;
+        }
+        tile.push(came_from);
+        for next_came_from in registry.get_outgoing(map[row as usize][col as usize], came_from) {
+            stack.push(advance(row, col, next_came_from));
         }
     }
+    beams
+}
 
+fn render_beams(map: &[Vec<char>], beams: &[Vec<Vec<CameFrom>>]) -> String {
     map
         .iter()
+        .zip(beams.iter())
+        .map(
+            |(line, beams_line)|
+                line
+                    .iter()
+                    .zip(beams_line.iter())
+                    .map(
+                        |(&char, tile)|
+                            match (char, &tile[..]) {
+                                ('.', [CameFrom::Left]) => '>',
+                                ('.', [CameFrom::Top]) => 'v',
+                                ('.', [CameFrom::Right]) => '<',
+                                ('.', [CameFrom::Bottom]) => '^',
+                                ('.', [_, ..]) => char::from_digit(tile.len() as u32, 10).unwrap(),
+                                _ => char,
+                            }
+                    )
+                    .collect::<String>()
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_energized(beams: &[Vec<Vec<CameFrom>>]) -> String {
+    beams
+        .iter()
+        .map(|line| line.iter().map(|tile| if tile.is_empty() { '.' } else { '#' }).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_render_beams() {
+    let input = r"
+.|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....
+";
+    let map = parse_input(input);
+    let beams = trace_beams(&map, (0, 0, CameFrom::Left), &ElementRegistry::standard());
+    let expected = r"
+>|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_beams(&map, &beams));
+    let expected = "
+######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_energized(&beams));
+}
+
+// Tarjan's algorithm with an explicit call stack, returns components in reverse topological order
+fn get_strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let nodes_count = edges.len();
+    let mut index = vec![None; nodes_count];
+    let mut low_link = vec![0; nodes_count];
+    let mut on_stack = vec![false; nodes_count];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut next_index = 0;
+    for root in 0..nodes_count {
+        if index[root].is_some() {
+            continue;
+        }
+        index[root] = Some(next_index);
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        let mut calls = vec![(root, 0)];
+        while let Some((node, edge)) = calls.pop() {
+            if edge < edges[node].len() {
+                calls.push((node, edge + 1));
+                let next = edges[node][edge];
+                match index[next] {
+                    None => {
+                        index[next] = Some(next_index);
+                        low_link[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        calls.push((next, 0));
+                    },
+                    Some(next_index) => if on_stack[next] {
+                        low_link[node] = low_link[node].min(next_index);
+                    },
+                }
+                continue;
+            }
+            if Some(low_link[node]) == index[node] {
+                let mut component = vec![];
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = calls.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+        }
+    }
+    components
+}
+
+#[test]
+fn test_get_strongly_connected_components() {
+    let edges = vec![vec![1], vec![2], vec![0, 3], vec![4], vec![3, 5], vec![]];
+    let mut components = get_strongly_connected_components(&edges);
+    for component in components.iter_mut() {
+        component.sort();
+    }
+    assert_eq!(vec![vec![5], vec![3, 4], vec![0, 1, 2]], components);
+
+    assert_eq!(vec![vec![0]], get_strongly_connected_components(&[vec![0]]));
+}
+
+#[test]
+fn test_beam_graph() {
+    let mut random = XorShift::new(0x2545f4914f6cdd1d);
+    let registry = ElementRegistry::extended();
+    let chars = ['.', '.', '.', '.', '\\', '/', '|', '-', 'X', 'E', 'S', 'W', 'N', '+'];
+    for _ in 0..50 {
+        let rows_count = 1 + random.below(12);
+        let cols_count = 1 + random.below(12);
+        let map = (0..rows_count)
+            .map(|_| (0..cols_count).map(|_| chars[random.below(chars.len())]).collect())
+            .collect::<Vec<Vec<char>>>();
+        let graph = BeamGraph::new(&map, &registry);
+        for start_from in get_entry_points(&map) {
+            let directly = trace_beams(&map, start_from, &registry).iter().flatten().filter(|x| !x.is_empty()).count();
+            assert_eq!(directly, graph.count_energized(start_from));
+        }
+    }
+}
+
+#[test]
+fn test_beam_graph_loop() {
+    // the splitter sends both beams into the mirror loop that passes through it
+    let input = r"
+.....
+./-\.
+.|.|.
+.\-/.
+";
+    let map = parse_input(input);
+    let registry = ElementRegistry::standard();
+    let graph = BeamGraph::new(&map, &registry);
+    assert_eq!(9, graph.count_energized((2, 0, CameFrom::Left)));
+    assert_eq!(vec![9, 3], graph.count_energized_all(&[(2, 0, CameFrom::Left), (1, 4, CameFrom::Right)]));
+}
+
+#[test]
+fn test_beam_graph_large() {
+    // a contraption much bigger than the puzzle input, where keeping a tile set per node would
+    // take gigabytes, checked on a few entry points against direct tracing
+    let mut random = XorShift::new(0x853c49e6748fea9b);
+    let chars = ['\\', '/', '|', '-'];
+    let size = 400;
+    let map = (0..size)
         .map(
-            |row|
-                row.iter().map(
-                    |&x|
-                        ((x != '.') && (x != '\\') && (x != '/') && (x != '|') && (x != '-')) as usize
-                )
-                    .sum::<usize>()
+            |_|
+                (0..size)
+                    .map(|_| if random.below(10) == 0 { chars[random.below(chars.len())] } else { '.' })
+                    .collect()
         )
-        .sum()
+        .collect::<Vec<Vec<char>>>();
+    let registry = ElementRegistry::standard();
+    let graph = BeamGraph::new(&map, &registry);
+    let entry_points = get_entry_points(&map);
+    let counts = graph.count_energized_all(&entry_points);
+    assert_eq!(4 * size, counts.len());
+    for index in (0..entry_points.len()).step_by(97) {
+        let directly = trace_beams(&map, entry_points[index], &registry).iter().flatten().filter(|x| !x.is_empty()).count();
+        assert_eq!(directly, counts[index]);
+    }
 }
 
 #[test]
@@ -160,9 +759,9 @@ fn test_process_input() {
 .|....-|.\
 ..//.|....
 ";
-    let result = process_input_pt1(input);
+    let result = process_input_pt1(input, &ElementRegistry::standard());
     assert_eq!(46, result);
 
-    let result = process_input_pt2(input);
+    let result = process_input_pt2(input, &ElementRegistry::standard());
     assert_eq!(51, result);
 }
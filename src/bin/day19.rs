@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fs::read_to_string;
+use rust_aoc_2023::interval_set::IntervalSet;
 
 fn main() {
     let file_contents = read_to_string("inputs/day19.txt").unwrap();
@@ -25,18 +26,12 @@ struct Step<'a> {
     to_label: &'a str,
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Range {
-    from: usize,
-    to: usize,
-}
-
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct Ranges {
-    x: Range,
-    m: Range,
-    a: Range,
-    s: Range,
+    x: IntervalSet<usize>,
+    m: IntervalSet<usize>,
+    a: IntervalSet<usize>,
+    s: IntervalSet<usize>,
 }
 
 fn process_input_pt1(input: &str) -> usize {
@@ -56,10 +51,10 @@ fn process_input_pt2(input: &str) -> usize {
     let mut stack = vec![(
         "in",
         Ranges{
-            x: Range{from: 1, to: 4000},
-            m: Range{from: 1, to: 4000},
-            a: Range{from: 1, to: 4000},
-            s: Range{from: 1, to: 4000},
+            x: IntervalSet::from(1..4001),
+            m: IntervalSet::from(1..4001),
+            a: IntervalSet::from(1..4001),
+            s: IntervalSet::from(1..4001),
         }
     )];
     let mut iterations = 0;
@@ -70,11 +65,7 @@ fn process_input_pt2(input: &str) -> usize {
             panic!("too many iterations {iterations}");
         }
         if label == "A" {
-            possible_combinations +=
-                (ranges.x.to - ranges.x.from + 1)
-                * (ranges.m.to - ranges.m.from + 1)
-                * (ranges.a.to - ranges.a.from + 1)
-                * (ranges.s.to - ranges.s.from + 1);
+            possible_combinations += ranges.x.len() * ranges.m.len() * ranges.a.len() * ranges.s.len();
             continue;
         }
         if label == "R" {
@@ -94,23 +85,18 @@ fn process_input_pt2(input: &str) -> usize {
                 's' => (&mut split_ranges.s, &mut ranges.s),
                 _ => panic!("unexpected param value {} in workflow {label}", step.param),
             };
-            let (can_split, can_continue) = match step.sign {
+            let (split_part, continue_part) = match step.sign {
                 '>' => {
-                    let can_split = range_split.to > step.value;
-                    let can_continue = range_continue.from <= step.value;
-                    range_split.from = step.value + 1;
-                    range_continue.to = step.value;
-                    (can_split, can_continue)
-                },
-                '<' => {
-                    let can_split = range_split.from < step.value;
-                    let can_continue = range_continue.to >= step.value;
-                    range_split.to = step.value - 1;
-                    range_continue.from = step.value;
-                    (can_split, can_continue)
+                    let (below, above) = range_continue.split_at(step.value + 1);
+                    (above, below)
                 },
+                '<' => range_continue.split_at(step.value),
                 _ => panic!("unexpected sign value {} in workflow {label}", step.sign),
             };
+            let can_split = !split_part.is_empty();
+            let can_continue = !continue_part.is_empty();
+            *range_split = split_part;
+            *range_continue = continue_part;
             if can_split {
                 stack.push((step.to_label, split_ranges));
             }
@@ -122,7 +108,7 @@ fn process_input_pt2(input: &str) -> usize {
     possible_combinations
 }
 
-fn parse_workflows_map(input: &str) -> HashMap<&str, Vec<Step>> {
+fn parse_workflows_map(input: &str) -> HashMap<&str, Vec<Step<'_>>> {
     let mut workflows_map = HashMap::new();
     for workflow in input.split('\n') {
         let (title, rest) = workflow.split_once('{').unwrap();
@@ -160,7 +146,7 @@ hdj{m>838:A,pv}
     assert_eq!(167409079868000, result);
 }
 
-fn parse_step(line: &str) -> Step {
+fn parse_step(line: &str) -> Step<'_> {
     if let Some((condition, to_label)) = line.split_once(':') {
         let mut chars = condition.chars();
         let param = chars.next().unwrap();
@@ -265,13 +251,13 @@ hdj{m>838:A,pv}
     let workflows_map = parse_workflows_map(workflows);
 
     let part = parse_part("{x=787,m=2655,a=1222,s=2876}");
-    assert_eq!(true, is_part_accepted(&part, &workflows_map));
+    assert!(is_part_accepted(&part, &workflows_map));
     let part = parse_part("{x=1679,m=44,a=2067,s=496}");
-    assert_eq!(false, is_part_accepted(&part, &workflows_map));
+    assert!(!is_part_accepted(&part, &workflows_map));
     let part = parse_part("{x=2036,m=264,a=79,s=2244}");
-    assert_eq!(true, is_part_accepted(&part, &workflows_map));
+    assert!(is_part_accepted(&part, &workflows_map));
     let part = parse_part("{x=2461,m=1339,a=466,s=291}");
-    assert_eq!(false, is_part_accepted(&part, &workflows_map));
+    assert!(!is_part_accepted(&part, &workflows_map));
     let part = parse_part("{x=2127,m=1623,a=2188,s=1013}");
-    assert_eq!(true, is_part_accepted(&part, &workflows_map));
+    assert!(is_part_accepted(&part, &workflows_map));
 }
@@ -1,6 +1,8 @@
 use std::cmp;
 use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs::read_to_string;
+use rust_aoc_2023::interval_set::IntervalSet;
 
 fn main() {
     let file_contents = read_to_string("inputs/day22.txt").unwrap();
@@ -8,107 +10,316 @@ fn main() {
     println!("{}", result);
     let result = process_input(&file_contents, true);
     println!("{}", result);
+
+    if let Some(removed) = env::args().find_map(|x| x.strip_prefix("--remove=").map(String::from)) {
+        let mut bricks = file_contents.trim().lines().map(parse_brick).collect::<Vec<_>>();
+        settle_bricks(&mut bricks);
+        let removed = parse_removed(&removed, bricks.len());
+        let (new_bricks, moved) = settle_without(&bricks, &removed);
+        for axis in 0..2 {
+            println!("before:\n{}", render_side_view(&bricks, &[], axis));
+            println!("after:\n{}", render_side_view(&new_bricks, &removed, axis));
+        }
+        for (brick_num, fall) in moved {
+            println!("brick {} falls by {}", brick_num, fall);
+        }
+    }
 }
 
-#[derive(Debug)]
+// comma separated brick numbers, counted from 1 in the settled order
+fn parse_removed(line: &str, bricks_count: usize) -> Vec<usize> {
+    line
+        .split(',')
+        .map(|x| match x.trim().parse::<usize>() {
+            Ok(brick_num) if (1..=bricks_count).contains(&brick_num) => brick_num,
+            _ => panic!("unexpected brick {}", x),
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_removed() {
+    assert_eq!([1, 6], parse_removed("1,6", 7)[..]);
+    assert_eq!([7], parse_removed(" 7", 7)[..]);
+}
+
+#[test]
+#[should_panic(expected = "unexpected brick 8")]
+fn test_parse_removed_unknown() {
+    parse_removed("1,8", 7);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 struct Brick([usize; 3], [usize; 3]);
 
+#[derive(Debug)]
+struct SupportGraph {
+    below: Vec<Vec<usize>>,
+    dominated: Vec<Vec<usize>>,
+    dominated_count: Vec<usize>,
+}
+
+impl SupportGraph {
+    // node 0 is the ground, bricks are numbered from 1 in the order they have settled,
+    // so every brick is supported only by bricks with a smaller number
+    fn new(below: Vec<Vec<usize>>) -> Self {
+        let size = below.len();
+        let mut dominator = vec![0; size];
+        let mut depth = vec![0; size];
+        for brick_num in 1..size {
+            let mut iter = below[brick_num].iter().copied();
+            let first = iter.next().unwrap();
+            let common = iter.fold(first, |x, y| common_dominator(&dominator, &depth, x, y));
+            dominator[brick_num] = common;
+            depth[brick_num] = depth[common] + 1;
+        }
+
+        let mut dominated = vec![vec![]; size];
+        let mut dominated_count = vec![0; size];
+        for brick_num in (1..size).rev() {
+            let parent = dominator[brick_num];
+            dominated[parent].push(brick_num);
+            dominated_count[parent] += dominated_count[brick_num] + 1;
+        }
+
+        Self{below, dominated, dominated_count}
+    }
+
+    fn bricks_count(&self) -> usize {
+        self.below.len() - 1
+    }
+
+    fn safe_to_disintegrate(&self) -> Vec<usize> {
+        (1..=self.bricks_count())
+            .filter(|&brick_num| self.chain_reaction_size(brick_num) == 0)
+            .collect()
+    }
+
+    fn chain_reaction_size(&self, brick_num: usize) -> usize {
+        self.dominated_count[brick_num]
+    }
+
+    #[allow(dead_code)]
+    fn dependent_bricks(&self, brick_num: usize) -> Vec<usize> {
+        let mut result = vec![];
+        let mut stack = self.dominated[brick_num].clone();
+        while let Some(current) = stack.pop() {
+            result.push(current);
+            stack.extend(&self.dominated[current]);
+        }
+        result.sort();
+        result
+    }
+}
+
+fn common_dominator(dominator: &[usize], depth: &[usize], mut x: usize, mut y: usize) -> usize {
+    while x != y {
+        if depth[x] > depth[y] {
+            x = dominator[x];
+        } else {
+            y = dominator[y];
+        }
+    }
+    x
+}
+
 fn process_input(input: &str, is_pt2: bool) -> usize {
     let mut bricks = input.trim().lines().map(parse_brick).collect::<Vec<_>>();
-    let sizes = get_field_sizes(&bricks);
-    let mut field = vec![vec![vec![0; sizes[2]]; sizes[1]]; sizes[0]];
+    let support_graph = settle_bricks(&mut bricks);
+    if is_pt2 {
+        (1..=support_graph.bricks_count())
+            .map(|brick_num| support_graph.chain_reaction_size(brick_num))
+            .sum()
+    } else {
+        support_graph.safe_to_disintegrate().len()
+    }
+}
+
+fn settle_bricks(bricks: &mut [Brick]) -> SupportGraph {
     bricks.sort_by(compare_bricks);
-    let mut bricks_below_map = HashMap::new();
-    for (index, brick) in bricks.iter_mut().enumerate() {
+    let mut bricks_below_list = drop_bricks(bricks, &[]);
+    bricks_below_list.insert(0, vec![]);
+    SupportGraph::new(bricks_below_list)
+}
+
+fn settle_without(bricks: &[Brick], removed: &[usize]) -> (Vec<Brick>, Vec<(usize, usize)>) {
+    let mut new_bricks = bricks.to_vec();
+    drop_bricks(&mut new_bricks, removed);
+    let moved = bricks
+        .iter()
+        .zip(new_bricks.iter())
+        .enumerate()
+        .filter(|&(_, (old, new))| old.0[2] != new.0[2])
+        .map(|(index, (old, new))| (index + 1, old.0[2] - new.0[2]))
+        .collect();
+    (new_bricks, moved)
+}
+
+fn drop_bricks(bricks: &mut [Brick], removed: &[usize]) -> Vec<Vec<usize>> {
+    let removed = removed.iter().collect::<HashSet<_>>();
+    let mut order = (0..bricks.len())
+        .filter(|index| !removed.contains(&(index + 1)))
+        .collect::<Vec<_>>();
+    order.sort_by(|&x, &y| compare_bricks(&bricks[x], &bricks[y]));
+    let mut top_map = HashMap::new();
+    let mut bricks_below_list = vec![vec![]; bricks.len()];
+    for index in order {
         let brick_num = index + 1;
+        let brick = &mut bricks[index];
 
-        let mut z_iter = brick.0[2];
-        loop {
-            let bricks_below_current = get_bricks_below(&field, brick, z_iter);
-            if bricks_below_current.len() > 0 {
-                bricks_below_map.insert(brick_num, bricks_below_current);
-                break;
-            } else {
-                z_iter -= 1;
-            }
-        }
-        let shift = brick.0[2] - z_iter;
-        brick.0[2] = z_iter;
+        let (height_below, bricks_below) = get_bricks_below(&top_map, brick);
+        bricks_below_list[index] = bricks_below;
+        let shift = brick.0[2] - height_below - 1;
+        brick.0[2] -= shift;
         brick.1[2] -= shift;
 
         for x in brick.0[0]..=brick.1[0] {
             for y in brick.0[1]..=brick.1[1] {
-                for z in brick.0[2]..=brick.1[2] {
-                    field[x][y][z] = brick_num;
-                }
+                top_map.insert((x, y), (brick.1[2], brick_num));
             }
         }
     }
+    bricks_below_list
+}
 
-    let mut bricks_above_map = HashMap::new();
-    for (index, brick) in bricks.iter().enumerate() {
-        let brick_num = index + 1;
-        bricks_above_map.insert(brick_num, get_bricks_above(&field, brick));
-    }
+#[test]
+fn test_settle_without() {
+    let input = "
+1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9
+";
+    let mut bricks = input.trim().lines().map(parse_brick).collect::<Vec<_>>();
+    settle_bricks(&mut bricks);
 
-    if is_pt2 {
-        let mut fall_count = 0;
-        for (index, _) in bricks.iter().enumerate() {
-            let brick_num = index + 1;
-            let mut fall_bricks = bricks_above_map.get(&brick_num)
-                .unwrap()
-                .iter()
-                .filter(
-                    |brick_above|
-                        bricks_below_map.get(brick_above).unwrap().len() <= 1
-                )
-                .copied()
-                .collect::<HashSet<_>>();
-            let mut above_bricks = fall_bricks
-                .iter()
-                .map(|brick_num| bricks_above_map.get(brick_num).unwrap())
-                .flatten()
-                .copied()
-                .collect::<HashSet<_>>();
-            fall_bricks.insert(brick_num);
-            loop {
-                let mut has_new_falls = false;
-                let mut new_above_bricks = HashSet::<usize>::new();
-                for above_brick in above_bricks.iter() {
-                    let bricks_below_above = bricks_below_map.get(above_brick).unwrap();
-                    if bricks_below_above.is_subset(&fall_bricks) {
-                        has_new_falls = true;
-                        fall_bricks.insert(*above_brick);
-                        new_above_bricks.extend(bricks_above_map.get(above_brick).unwrap());
-                    }
-                }
-                if !has_new_falls {
-                    break;
+    let (new_bricks, moved) = settle_without(&bricks, &[1]);
+    assert_eq!([(2, 1), (3, 1), (4, 1), (5, 1), (6, 1), (7, 1)], moved[..]);
+    assert_eq!([[1, 1, 4], [1, 1, 5]], [new_bricks[6].0, new_bricks[6].1]);
+    assert_eq!(bricks[0], new_bricks[0]);
+
+    let (_, moved) = settle_without(&bricks, &[6]);
+    assert_eq!([(7, 3)], moved[..]);
+    let (_, moved) = settle_without(&bricks, &[2]);
+    assert!(moved.is_empty());
+    let (_, moved) = settle_without(&bricks, &[2, 3]);
+    assert_eq!([(4, 2), (5, 2), (6, 2), (7, 2)], moved[..]);
+}
+
+fn render_side_view(bricks: &[Brick], removed: &[usize], axis: usize) -> String {
+    let removed = removed.iter().collect::<HashSet<_>>();
+    let axis_name = match axis {
+        0 => 'x',
+        1 => 'y',
+        _ => panic!("unexpected axis {axis}"),
+    };
+    let visible = bricks
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !removed.contains(&(index + 1)))
+        .collect::<Vec<_>>();
+    let width = visible.iter().map(|(_, brick)| brick.1[axis] + 1).max().unwrap_or(1);
+    let height = visible.iter().map(|(_, brick)| brick.1[2]).max().unwrap_or(0);
+    let mut view = vec![vec!['.'; width]; height + 1];
+    let mut occupied = vec![IntervalSet::new(); height + 1];
+    for (index, brick) in visible {
+        let label = if index < 26 { (b'A' + index as u8) as char } else { '#' };
+        let span = get_brick_span(brick, axis);
+        for z in brick.0[2]..=brick.1[2] {
+            let overlap = occupied[z].intersection(&span);
+            for (cells, char) in [(&span, label), (&overlap, '?')] {
+                for range in cells.ranges() {
+                    view[z][range.clone()].fill(char);
                 }
-                above_bricks = above_bricks.difference(&fall_bricks).copied().collect();
-                above_bricks.extend(new_above_bricks);
             }
-            let fall_for_current = fall_bricks.iter().count() - 1;
-            fall_count += fall_for_current;
+            occupied[z] = occupied[z].union(&span);
         }
-        fall_count
-    } else {
-        bricks
-            .iter()
-            .enumerate()
-            .filter(
-                |(i, _)|
-                    bricks_above_map.get(&(i + 1))
-                        .unwrap()
-                        .iter()
-                        .find(
-                            |brick_above|
-                                bricks_below_map.get(brick_above).unwrap().len() <= 1
-                        )
-                        .is_none()
-            )
-            .count()
     }
+
+    let mut result = format!("{}{axis_name}\n", " ".repeat(width / 2));
+    result.extend((0..width).map(|i| char::from_digit((i % 10) as u32, 10).unwrap()));
+    result.push('\n');
+    for z in (1..=height).rev() {
+        result.extend(view[z].iter());
+        result.push_str(&format!(" {z}\n"));
+    }
+    result.push_str(&"-".repeat(width));
+    result.push_str(" 0\n");
+    result
+}
+
+fn get_brick_span(brick: &Brick, axis: usize) -> IntervalSet<usize> {
+    IntervalSet::from(brick.0[axis]..(brick.1[axis] + 1))
+}
+
+#[test]
+fn test_render_side_view() {
+    let input = "
+1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9
+";
+    let mut bricks = input.trim().lines().map(parse_brick).collect::<Vec<_>>();
+    let expected = "
+ x
+012
+.G. 9
+.G. 8
+... 7
+FFF 6
+..E 5
+D.. 4
+CCC 3
+BBB 2
+.A. 1
+--- 0
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_side_view(&bricks, &[], 0));
+    let expected = "
+ y
+012
+.G. 9
+.G. 8
+... 7
+.F. 6
+EEE 5
+DDD 4
+..C 3
+B.. 2
+AAA 1
+--- 0
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_side_view(&bricks, &[], 1));
+
+    settle_bricks(&mut bricks);
+    let expected = "
+ x
+012
+.G. 6
+.G. 5
+FFF 4
+D.E 3
+??? 2
+.A. 1
+--- 0
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_side_view(&bricks, &[], 0));
+    let (bricks, _) = settle_without(&bricks, &[1, 6]);
+    let expected = "
+ x
+012
+DGE 2
+??? 1
+--- 0
+";
+    assert_eq!(expected.strip_prefix('\n').unwrap(), render_side_view(&bricks, &[1, 6], 0));
 }
 
 fn parse_brick(line: &str) -> Brick {
@@ -131,50 +342,29 @@ fn parse_coordinates(line: &str) -> [usize; 3] {
     ]
 }
 
-fn get_field_sizes(bricks: &[Brick]) -> [usize; 3] {
-    let mut iter = (0..3).map(
-        |coord|
-            bricks.iter().map(|x| cmp::max(x.0[coord], x.1[coord]) + 1).max().unwrap()
-    );
-    [
-        iter.next().unwrap(),
-        iter.next().unwrap(),
-        iter.next().unwrap(),
-    ]
-}
-
 fn compare_bricks(x: &Brick, y: &Brick) -> cmp::Ordering {
-    x.0[2].partial_cmp(&y.0[2]).unwrap()
+    x.0[2].cmp(&y.0[2])
 }
 
-fn get_bricks_below(field: &[Vec<Vec<usize>>], brick: &Brick, z: usize) -> HashSet<usize> {
-    let mut below = HashSet::new();
-    if z <= 1 {
-        below.insert(0);
-        return below;
-    }
+fn get_bricks_below(top_map: &HashMap<(usize, usize), (usize, usize)>, brick: &Brick) -> (usize, Vec<usize>) {
+    let mut height_below = 0;
+    let mut below = vec![0];
     for x in brick.0[0]..=brick.1[0] {
         for y in brick.0[1]..=brick.1[1] {
-            let brick_num_below = field[x][y][z - 1];
-            if brick_num_below > 0 {
-                below.insert(brick_num_below);
+            let Some(&(height, brick_num)) = top_map.get(&(x, y)) else {
+                continue;
+            };
+            if height > height_below {
+                height_below = height;
+                below.clear();
             }
-        }
-    }
-    below.into_iter().collect()
-}
-
-fn get_bricks_above(field: &[Vec<Vec<usize>>], brick: &Brick) -> HashSet<usize> {
-    let mut above = HashSet::new();
-    for x in brick.0[0]..=brick.1[0] {
-        for y in brick.0[1]..=brick.1[1] {
-            let brick_num_above = field[x][y][brick.1[2] + 1];
-            if brick_num_above > 0 {
-                above.insert(brick_num_above);
+            if (height == height_below) && !below.contains(&brick_num) {
+                below.push(brick_num);
             }
         }
     }
-    above
+    below.sort();
+    (height_below, below)
 }
 
 #[test]
@@ -193,3 +383,45 @@ fn test_process_input() {
     let result = process_input(input, true);
     assert_eq!(7, result);
 }
+
+#[test]
+fn test_support_graph() {
+    let input = "
+1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9
+";
+    let mut bricks = input.trim().lines().map(parse_brick).collect::<Vec<_>>();
+    let support_graph = settle_bricks(&mut bricks);
+    assert_eq!(7, support_graph.bricks_count());
+    assert_eq!([2, 3, 4, 5, 7], support_graph.safe_to_disintegrate()[..]);
+    assert_eq!(6, support_graph.chain_reaction_size(1));
+    assert_eq!(1, support_graph.chain_reaction_size(6));
+    assert_eq!(0, support_graph.chain_reaction_size(2));
+    assert_eq!([2, 3, 4, 5, 6, 7], support_graph.dependent_bricks(1)[..]);
+    assert_eq!([7], support_graph.dependent_bricks(6)[..]);
+    assert!(support_graph.dependent_bricks(4).is_empty());
+    assert_eq!([4, 5], support_graph.below[6][..]);
+    assert_eq!([[1, 1, 5], [1, 1, 6]], [bricks[6].0, bricks[6].1]);
+}
+
+#[test]
+fn test_settle_bricks_sparse() {
+    let input = "
+1000000,1000000,500000000~1000000,1000002,500000000
+1000000,1000001,900000000~1000005,1000001,900000000
+0,0,7~0,0,9
+";
+    let mut bricks = input.trim().lines().map(parse_brick).collect::<Vec<_>>();
+    let support_graph = settle_bricks(&mut bricks);
+    assert_eq!([[0, 0, 1], [0, 0, 3]], [bricks[0].0, bricks[0].1]);
+    assert_eq!(1, bricks[1].0[2]);
+    assert_eq!(2, bricks[2].0[2]);
+    assert_eq!([0], support_graph.below[2][..]);
+    assert_eq!([2], support_graph.below[3][..]);
+    assert_eq!([1, 3], support_graph.safe_to_disintegrate()[..]);
+}
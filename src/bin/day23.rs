@@ -1,6 +1,10 @@
 use std::{cmp, thread};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::env;
 use std::fs::read_to_string;
+use rust_aoc_2023::junction_graph::{EdgeId, JunctionGraph, NodeId, Pos};
+#[cfg(test)]
+use rust_aoc_2023::random::XorShift;
 
 fn main() {
     let file_contents = read_to_string("inputs/day23.txt").unwrap();
@@ -8,11 +12,35 @@ fn main() {
     println!("{}", result);
     let result = process_input(&file_contents, true);
     println!("{}", result);
+
+    if env::args().any(|x| x == "--render") {
+        let field = parse_input(&file_contents);
+        for is_pt2 in [false, true] {
+            let hike = find_longest_hike(&field, is_pt2, true);
+            println!("{}", render_hike(&field, &hike));
+            println!();
+        }
+    }
 }
 
 fn process_input(input: &str, is_pt2: bool) -> usize {
-    let field = input.trim().lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    let field = parse_input(input);
+    find_longest_hike(&field, is_pt2, true).steps
+}
+
+fn parse_input(input: &str) -> Vec<Vec<char>> {
+    input.trim().lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>()
+}
 
+// `junctions` are the nodes passed from the start to the last tile, `route` every tile stepped on after the start
+#[derive(Debug, PartialEq)]
+struct Hike {
+    steps: usize,
+    junctions: Vec<Pos>,
+    route: Vec<Pos>,
+}
+
+fn find_longest_hike(field: &[Vec<char>], is_pt2: bool, is_pruned: bool) -> Hike {
     let start_row = 0;
     let start_col = field[start_row].iter().position(|&x| x == '.').unwrap();
     let start_pos = (start_row as isize, start_col as isize);
@@ -20,139 +48,109 @@ fn process_input(input: &str, is_pt2: bool) -> usize {
     let last_col = field[last_row].iter().position(|&x| x == '.').unwrap();
     let last_pos = (last_row as isize, last_col as isize);
 
-    let mut graph = construct_graph(&field, start_pos, is_pt2);
-
-    /*
-    optimisation: the graph is a square grid
-    for nodes that are on the perimeter of that grid, we can remove the edges that are leading backwards
-    because if such an edge is followed, it would not be possible to arrive at the last node without visiting any node a second time
-     */
-    let node_after_start = *graph.get(&start_pos).unwrap().keys().next().unwrap();
-    let trim_start_nodes = graph
-        .get(&node_after_start)
-        .unwrap()
-        .keys()
-        .filter(|&node| *node != start_pos)
-        .copied()
-        .collect::<Vec<_>>();
-    for trim_start_node in trim_start_nodes {
-        let mut current_node = trim_start_node;
-        loop {
-            let next_nodes = graph.get(&current_node).unwrap();
-            if next_nodes.contains_key(&last_pos) {
-                break;
-            }
-            let next_edge_node = next_nodes
-                .keys()
-                .filter(|&node| *node != node_after_start)
-                .find(|&node| graph.get(node).unwrap().len() < 4)
-                .copied()
-                .unwrap();
-            graph.get_mut(&next_edge_node).unwrap().remove(&current_node);
-            current_node = next_edge_node;
-        }
+    let mut graph = construct_graph(field, is_pt2);
+    let start_node = graph.node_id(start_pos).unwrap();
+    let last_node = graph.node_id(last_pos).unwrap();
+    if is_pruned {
+        prune_perimeter(&mut graph, start_node, last_node);
     }
 
-    /*if is_pt2 {
-        for (from_node, to_map) in graph.iter() {
-            for (to_node, _steps) in to_map {
-                //println!("{from_node:?} {to_node:?} {_steps}");
-                println!("{}_{} {}_{}", from_node.0, from_node.1, to_node.0, to_node.1);
-            }
-        }
-    }*/
-    get_longest_path_steps(&graph, start_pos, last_row as isize, HashSet::new(), 0)
+    let (steps, mut edges) = get_longest_path(&graph, start_node, last_node, HashSet::new(), 0)
+        .unwrap_or_else(|| panic!("no hike reaches the last tile"));
+    edges.reverse();
+    let mut junctions = vec![start_pos];
+    let mut route = vec![];
+    for edge in edges {
+        let edge = graph.edge(edge);
+        junctions.push(graph.node_pos(edge.to));
+        route.extend_from_slice(&edge.path);
+    }
+    Hike{steps, junctions, route}
 }
 
-fn construct_graph(field: &[Vec<char>], start_pos: (isize, isize), is_pt2: bool) -> HashMap<(isize, isize), HashMap<(isize, isize), usize>> {
-    let mut edges = HashMap::new();
-    edges.insert(start_pos, HashMap::new());
-    let mut stack = vec![(start_pos, (start_pos.0 + 1, start_pos.1))];
-    let mut valid_next = Vec::with_capacity(4);
-    let rows_count = field.len() as isize;
-    let cols_count = field[0].len() as isize;
-    while let Some((from_node, current_pos)) = stack.pop() {
-        let mut steps= 0;
-        let mut prev_pos = from_node;
-        let mut current_pos = current_pos;
-        loop {
-            steps += 1;
-            if current_pos.0 == (rows_count - 1) {
-                edges.get_mut(&from_node).unwrap().insert(current_pos, steps);
-                break;
-            }
-            let possible_next = [
-                (current_pos.0 + 1, current_pos.1),
-                (current_pos.0, current_pos.1 + 1),
-                (current_pos.0 - 1, current_pos.1),
-                (current_pos.0, current_pos.1 - 1),
-            ];
-            valid_next.truncate(0);
-            for next_pos in possible_next {
-                let (next_row, next_col) = next_pos;
-                if (next_row < 0) || (next_row >= rows_count) {
-                    continue;
-                }
-                if (next_col < 0) || (next_col >= cols_count) {
-                    continue;
-                }
-                if (next_row == prev_pos.0) && (next_col == prev_pos.1) {
-                    continue;
-                }
-                let next_val = field[next_row as usize][next_col as usize];
-                if next_val == '#' {
-                    continue;
-                }
-                valid_next.push(next_pos);
-            }
-            if valid_next.len() == 1 {
-                prev_pos = current_pos;
-                current_pos = valid_next[0];
-                continue;
-            }
-
-            let to_node = current_pos;
-            edges.get_mut(&from_node).unwrap().insert(to_node, steps);
-
-            let is_already_processed = edges.get(&to_node).is_some();
-            if !is_already_processed {
-                edges.insert(to_node, HashMap::new());
-            }
-            let (prev_row, prev_col) = prev_pos;
-            let prev_val = field[prev_row as usize][prev_col as usize];
-            let can_go_back = is_pt2 || (prev_val == '.');
-            if can_go_back {
-                edges.get_mut(&to_node).unwrap().insert(from_node, steps);
-            }
-
-            if is_already_processed {
-                break;
-            }
+fn render_hike(field: &[Vec<char>], hike: &Hike) -> String {
+    let mut field = field.to_vec();
+    for &(row, col) in hike.route.iter() {
+        field[row as usize][col as usize] = 'O';
+    }
+    let (start_row, start_col) = hike.junctions[0];
+    field[start_row as usize][start_col as usize] = 'S';
+    field.iter().map(|x| x.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+}
 
-            for &next_pos in valid_next.iter() {
-                if !is_pt2 {
-                    let (next_row, next_col) = next_pos;
-                    let next_val = field[next_row as usize][next_col as usize];
-                    let (current_row, current_col) = current_pos;
-                    match next_val {
-                        '>' => if next_col <= current_col { continue; },
-                        '<' => if next_col >= current_col { continue; },
-                        'v' => if next_row <= current_row { continue; },
-                        '^' => if next_row >= current_row { continue; },
-                        _ => (),
-                    }
-                }
-                stack.push((to_node, next_pos));
-            }
-            break;
+/*
+optimisation: the start and the last node are on the outer face of the (planar) graph
+its boundary makes two sides going from the node after start to the last node
+if a hike goes back along one of the sides, towards the node after start, it gets enclosed between
+that side and the way it has taken so far, and it would not be possible to arrive at the last node
+without visiting any node a second time, so those edges can be removed
+returns false and leaves the graph as is when it does not have that shape
+ */
+fn prune_perimeter(graph: &mut JunctionGraph, start_node: NodeId, last_node: NodeId) -> bool {
+    if !graph.is_undirected() {
+        return false;
+    }
+    let Some(walk) = graph.walk_face(start_node) else {
+        return false;
+    };
+    // the walk is start, node after start, ..., last, ..., node after start, start
+    // without the start corridor it has to be a simple cycle, where the last node may hang off a corridor of its own
+    let mut cycle = walk[1..walk.len() - 2].to_vec();
+    let Some(mut last_index) = cycle.iter().position(|&x| x == last_node) else {
+        return false;
+    };
+    if (last_index > 0) && (cycle.get(last_index + 1) == Some(&cycle[last_index - 1])) {
+        cycle.drain(last_index..=last_index + 1);
+        last_index -= 1;
+    }
+    if cycle.iter().collect::<HashSet<_>>().len() != cycle.len() {
+        return false;
+    }
+    // two nodes only make a cycle when two corridors join them, otherwise one just hangs off the other
+    let is_cycle = match cycle[..] {
+        [_] => false,
+        [node1, node2] => graph.outgoing(node1).filter(|edge| edge.to == node2).count() > 1,
+        _ => true,
+    };
+    if !is_cycle {
+        return false;
+    }
+    let first_side = cycle[..=last_index].to_vec();
+    let mut second_side = cycle[last_index..].to_vec();
+    second_side.push(cycle[0]);
+    second_side.reverse();
+    for side in [first_side, second_side] {
+        for nodes in side.windows(2) {
+            graph.remove_edge(nodes[1], nodes[0]);
         }
     }
-    edges
+    true
 }
 
-fn get_longest_path_steps(graph: &HashMap<(isize, isize), HashMap<(isize, isize), usize>>, current_node: (isize, isize), end_row: isize, mut visited: HashSet<(isize, isize)>, nest_level: usize) -> usize {
-    if current_node.0 == end_row {
-        return 0;
+fn construct_graph(field: &[Vec<char>], is_pt2: bool) -> JunctionGraph {
+    let get_char = |(row, col): Pos| field[row as usize][col as usize];
+    // a slope can only be entered and left going downhill
+    let is_downhill = |pos: Pos, from: Pos, to: Pos| {
+        match get_char(pos) {
+            '>' => to.1 > from.1,
+            '<' => to.1 < from.1,
+            'v' => to.0 > from.0,
+            '^' => to.0 < from.0,
+            _ => true,
+        }
+    };
+    JunctionGraph::build(
+        field.len(),
+        field[0].len(),
+        |pos| get_char(pos) != '#',
+        |from, to| is_pt2 || (is_downhill(from, from, to) && is_downhill(to, from, to)),
+    )
+}
+
+// steps and the edges taken, from the last one back, None when every way from here ends up in a dead end
+fn get_longest_path(graph: &JunctionGraph, current_node: NodeId, last_node: NodeId, mut visited: HashSet<NodeId>, nest_level: usize) -> Option<(usize, Vec<EdgeId>)> {
+    if current_node == last_node {
+        return Some((0, vec![]));
     }
     visited.insert(current_node);
     /*
@@ -165,32 +163,43 @@ fn get_longest_path_steps(graph: &HashMap<(isize, isize), HashMap<(isize, isize)
     if nest_level < 4 {
         thread::scope(|s| {
             let mut threads = vec![];
-            for (to_node, edge_steps) in graph.get(&current_node).unwrap() {
-                if visited.contains(to_node) {
+            for &edge_id in graph.outgoing_ids(current_node) {
+                let edge = graph.edge(edge_id);
+                if visited.contains(&edge.to) {
                     continue;
                 }
+                let visited = visited.clone();
                 threads.push(s.spawn(
-                    || get_longest_path_steps(graph, *to_node, end_row, visited.clone(), nest_level + 1) + *edge_steps
+                    move || get_longest_path(graph, edge.to, last_node, visited, nest_level + 1)
+                        .map(|x| add_edge(x, edge_id, edge.steps))
                 ));
             }
-            let mut max_steps = 0;
+            let mut max_steps = None;
             for thread in threads {
                 max_steps = cmp::max(max_steps, thread.join().unwrap());
             }
             max_steps
         })
     } else {
-        let mut max_steps = 0;
-        for (to_node, edge_steps) in graph.get(&current_node).unwrap() {
-            if visited.contains(to_node) {
+        let mut max_steps = None;
+        for &edge_id in graph.outgoing_ids(current_node) {
+            let edge = graph.edge(edge_id);
+            if visited.contains(&edge.to) {
                 continue;
             }
-            max_steps = cmp::max(max_steps, get_longest_path_steps(graph, *to_node, end_row, visited.clone(), nest_level + 1) + *edge_steps);
+            let path = get_longest_path(graph, edge.to, last_node, visited.clone(), nest_level + 1)
+                .map(|x| add_edge(x, edge_id, edge.steps));
+            max_steps = cmp::max(max_steps, path);
         }
         max_steps
     }
 }
 
+fn add_edge((steps, mut edges): (usize, Vec<EdgeId>), edge: EdgeId, edge_steps: usize) -> (usize, Vec<EdgeId>) {
+    edges.push(edge);
+    (steps + edge_steps, edges)
+}
+
 #[test]
 fn test_process_input() {
     let input = "
@@ -223,3 +232,164 @@ fn test_process_input() {
     let result = process_input(input, true);
     assert_eq!(154, result);
 }
+
+#[test]
+fn test_render_hike() {
+    let input = "
+#.#####################
+#.......#########...###
+#######.#########.#.###
+###.....#.>.>.###.#.###
+###v#####.#v#.###.#.###
+###.>...#.#.#.....#...#
+###v###.#.#.#########.#
+###...#.#.#.......#...#
+#####.#.#.#######.#.###
+#.....#.#.#.......#...#
+#.#####.#.#.#########v#
+#.#...#...#...###...>.#
+#.#.#v#######v###.###v#
+#...#.>.#...>.>.#.###.#
+#####v#.#.###v#.#.###.#
+#.....#...#...#.#.#...#
+#.#########.###.#.#.###
+#...###...#...#...#.###
+###.###.#.###v#####v###
+#...#...#.#.>.>.#.>.###
+#.###.###.#.###.#.#v###
+#.....###...###...#...#
+#####################.#
+";
+    let field = parse_input(input);
+    let hike = find_longest_hike(&field, false, true);
+    let expected = "
+#S#####################
+#OOOOOOO#########...###
+#######O#########.#.###
+###OOOOO#OOO>.###.#.###
+###O#####O#O#.###.#.###
+###OOOOO#O#O#.....#...#
+###v###O#O#O#########.#
+###...#O#O#OOOOOOO#...#
+#####.#O#O#######O#.###
+#.....#O#O#OOOOOOO#...#
+#.#####O#O#O#########v#
+#.#...#OOO#OOO###OOOOO#
+#.#.#v#######O###O###O#
+#...#.>.#...>OOO#O###O#
+#####v#.#.###v#O#O###O#
+#.....#...#...#O#O#OOO#
+#.#########.###O#O#O###
+#...###...#...#OOO#O###
+###.###.#.###v#####O###
+#...#...#.#.>.>.#.>O###
+#.###.###.#.###.#.#O###
+#.....###...###...#OOO#
+#####################O#
+";
+    assert_eq!(expected.trim(), render_hike(&field, &hike));
+    assert_eq!(vec![(0, 1), (5, 3), (3, 11), (13, 13), (11, 21), (19, 19), (22, 21)], hike.junctions);
+
+    // every step of the dry hike goes to a neighbouring tile not stepped on before
+    let hike = find_longest_hike(&field, true, true);
+    assert_eq!(154, hike.route.len());
+    assert_eq!(Some(&(22, 21)), hike.route.last());
+    let mut prev_pos = hike.junctions[0];
+    for &pos in hike.route.iter() {
+        assert_eq!(1, (pos.0 - prev_pos.0).abs() + (pos.1 - prev_pos.1).abs());
+        assert_ne!('#', field[pos.0 as usize][pos.1 as usize]);
+        prev_pos = pos;
+    }
+    assert_eq!(155, hike.route.iter().chain(hike.junctions.iter()).collect::<HashSet<_>>().len());
+    assert_eq!(154, render_hike(&field, &hike).chars().filter(|&x| x == 'O').count());
+}
+
+#[test]
+fn test_prune_perimeter() {
+    let mut random = XorShift::new(0x9e3779b97f4a7c15);
+    let try_prune = |field: &[Vec<char>], is_pt2: bool| {
+        let mut graph = construct_graph(field, is_pt2);
+        let start_node = graph.node_id((0, 1)).unwrap();
+        let last_row = field.len() - 1;
+        let last_col = field[last_row].iter().position(|&x| x == '.').unwrap();
+        let last_node = graph.node_id((last_row as isize, last_col as isize)).unwrap();
+        prune_perimeter(&mut graph, start_node, last_node)
+    };
+    let mut pruned_count = 0;
+    for _ in 0..40 {
+        // carve a random spanning tree over the cells at odd coordinates
+        let cells_rows = 2 + random.below(4);
+        let cells_cols = 2 + random.below(4);
+        let mut field = vec![vec!['#'; 2 * cells_cols + 1]; 2 * cells_rows + 1];
+        let mut stack = vec![(1, 1)];
+        field[1][1] = '.';
+        while let Some(&(row, col)) = stack.last() {
+            let next = [(0, 2), (2, 0), (0, -2), (-2, 0)]
+                .iter()
+                .map(|&(d_row, d_col)| ((row as isize + d_row) as usize, (col as isize + d_col) as usize))
+                .filter(|&(r, c)| (r > 0) && (c > 0) && (r < 2 * cells_rows) && (c < 2 * cells_cols) && (field[r][c] == '#'))
+                .collect::<Vec<_>>();
+            if next.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let (next_row, next_col) = next[random.below(next.len())];
+            field[(row + next_row) / 2][(col + next_col) / 2] = '.';
+            field[next_row][next_col] = '.';
+            stack.push((next_row, next_col));
+        }
+        // most dead ends get opened into a neighbour cell, so that the outer face is often a simple cycle
+        for row in (1..2 * cells_rows).step_by(2) {
+            for col in (1..2 * cells_cols).step_by(2) {
+                let sides = [(row - 1, col), (row + 1, col), (row, col - 1), (row, col + 1)];
+                let walls = sides
+                    .into_iter()
+                    .filter(|&(r, c)| (r > 0) && (c > 0) && (r < 2 * cells_rows) && (c < 2 * cells_cols) && (field[r][c] == '#'))
+                    .collect::<Vec<_>>();
+                let is_dead_end = sides.iter().filter(|&&(r, c)| field[r][c] == '.').count() == 1;
+                if is_dead_end && (random.below(4) > 0) {
+                    let (wall_row, wall_col) = walls[random.below(walls.len())];
+                    field[wall_row][wall_col] = '.';
+                }
+            }
+        }
+        field[0][1] = '.';
+        field[2 * cells_rows][2 * cells_cols - 1] = '.';
+
+        let pruned = find_longest_hike(&field, true, true).steps;
+        let unpruned = find_longest_hike(&field, true, false).steps;
+        let maze = field.iter().map(|x| x.iter().collect::<String>()).collect::<Vec<_>>().join("\n");
+        assert_eq!(unpruned, pruned, "{}", maze);
+
+        if try_prune(&field, true) {
+            pruned_count += 1;
+        }
+    }
+    assert!(pruned_count > 10);
+
+    // with slopes the edges only go one way, and a boundary touching itself is not a simple cycle
+    let input = "
+#.#####
+#.....#
+#.###v#
+#.....#
+#####.#
+";
+    let field = parse_input(input);
+    assert!(!try_prune(&field, false));
+    assert!(try_prune(&field, true));
+    let input = "
+#.#####
+#.....#
+#.#.#.#
+#.....#
+###.###
+#.....#
+#.#.#.#
+#.....#
+#####.#
+";
+    let field = parse_input(input);
+    assert!(!try_prune(&field, true));
+    assert_eq!(20, process_input(input, true));
+}
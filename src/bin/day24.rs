@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::env;
 use std::fs::read_to_string;
 
 fn main() {
@@ -6,32 +8,62 @@ fn main() {
     println!("{}", result);
     let result = process_input_pt2(&file_contents);
     println!("{}", result);
+
+    let hailstorm = Hailstorm::parse(&file_contents);
+    if env::args().any(|x| x == "--approaches") {
+        let mut approaches = hailstorm.get_closest_approaches();
+        approaches.sort_by_key(|x| x.distance_squared);
+        for approach in approaches {
+            println!(
+                "{} and {}: distance {:.3} at time {:.3}",
+                approach.stone1, approach.stone2, approach.distance(), approach.time.to_f64(),
+            );
+        }
+    }
+    if let Some(rock) = env::args().find_map(|x| x.strip_prefix("--rock=").map(parse_stone)) {
+        match hailstorm.get_rock_collisions(&rock) {
+            Ok(times) => for (index, time) in times.iter().enumerate() {
+                println!("hits {} at time {}", index, time.to_f64());
+            },
+            Err(index) => println!("misses {}", index),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct Stone {
     pos: Position,
     vel: Velocity,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 struct Position {
     x: isize,
     y: isize,
     z: isize,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 struct Velocity {
     x: isize,
     y: isize,
     z: isize,
 }
 
+impl Stone {
+    fn position_at(&self, time: isize) -> Position {
+        Position{
+            x: self.pos.x + self.vel.x * time,
+            y: self.pos.y + self.vel.y * time,
+            z: self.pos.z + self.vel.z * time,
+        }
+    }
+}
+
 fn process_input_pt1(input: &str, coord_min: usize, coord_max: usize) -> usize {
     let stones = input.trim().lines().map(parse_stone).collect::<Vec<_>>();
-    let coord_min = coord_min as f64;
-    let coord_max = coord_max as f64;
+    let coord_min = coord_min as i128;
+    let coord_max = coord_max as i128;
     let mut count = 0;
     for (index, stone1) in stones.iter().enumerate() {
         for stone2 in &stones[index + 1..] {
@@ -61,70 +93,388 @@ fn parse_stone(line: &str) -> Stone {
     }
 }
 
-fn is_intersect_within(stone1: &Stone, stone2: &Stone, coord_min: f64, coord_max: f64) -> bool {
+// exact fraction with a positive denominator, kept in lowest terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+impl Ratio {
+    fn new(num: i128, den: i128) -> Self {
+        if den == 0 {
+            panic!("unexpected zero denominator");
+        }
+        let divisor = gcd(num.abs(), den.abs()) * den.signum();
+        Self{num: num / divisor, den: den / divisor}
+    }
+
+    fn from_int(value: i128) -> Self {
+        Self{num: value, den: 1}
+    }
+
+    fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    fn is_negative(&self) -> bool {
+        self.num < 0
+    }
+
+    // start + step * self
+    fn along(&self, start: i128, step: i128) -> Ratio {
+        Ratio::new(start * self.den + step * self.num, self.den)
+    }
+}
+
+impl Ord for Ratio {
+    // compares whole parts first and then the reciprocals of the remainders, so that nothing
+    // gets multiplied and the squared distances can use the full i128 range
+    fn cmp(&self, other: &Self) -> Ordering {
+        let whole1 = self.num.div_euclid(self.den);
+        let whole2 = other.num.div_euclid(other.den);
+        if whole1 != whole2 {
+            return whole1.cmp(&whole2);
+        }
+        match (self.num.rem_euclid(self.den), other.num.rem_euclid(other.den)) {
+            (0, 0) => Ordering::Equal,
+            (0, _) => Ordering::Less,
+            (_, 0) => Ordering::Greater,
+            (rem1, rem2) => Ratio{num: other.den, den: rem2}.cmp(&Ratio{num: self.den, den: rem1}),
+        }
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.max(1) } else { gcd(b, a % b) }
+}
+
+#[derive(Debug, PartialEq)]
+enum PathCrossing {
+    // the times at which each stone passes the crossing point, negative for the past
+    Point{t1: Ratio, t2: Ratio},
+    Parallel,
+    // both paths lie on the same line (or are the same standing point)
+    Coincident,
+}
+
+fn cross(ax: i128, ay: i128, bx: i128, by: i128) -> i128 {
+    ax * by - ay * bx
+}
+
+// crossing of the x/y paths, ignoring z
+fn get_path_crossing(stone1: &Stone, stone2: &Stone) -> PathCrossing {
     /*
-    (x - xa) / (xb - xa) = (y - ya) / (yb - ya)
-    xb - xa = vel.x
-    (x - pos.x) / vel.x = (y - pos.y) / vel.y
-    (x - pos.x)vel.y/vel.x = y - pos.y
-    (x - pos.x)vel.y/vel.x + pos.y = y
-    y = (vel.y/vel.x)x + (pos.y - pos.x(vel.y/vel.x))
-    set (vel.y/vel.x) = k
-    set (pos.y - pos.x(vel.y/vel.x)) = b
-    y = kx + b
-    find intersections
-    y = k1x + b1
-    y = k2x + b2
-    0 = (k1 - k2)x + b1 - b2
-    (k1 - k2)x = b2 - b1
-    x = (b2 - b1) / (k1 - k2)
-    if (k1 - k2) = 0 then they don't intersect
-     */
-    let k1 = stone1.vel.y as f64 / stone1.vel.x as f64;
-    let k2 = stone2.vel.y as f64 / stone2.vel.x as f64;
-    let k_diff = k1 - k2;
-    if k_diff.abs() < 0.0000001 {
-        //println!("no intersection");
-        return false;
-    }
-    let b1 = (stone1.pos.y as f64) - (stone1.pos.x as f64 * k1);
-    let b2 = (stone2.pos.y as f64) - (stone2.pos.x as f64 * k2);
-    let intersect_x = (b2 - b1) / (k1 - k2);
-    let intersect_y = (k1 * intersect_x) + b1;
-    if (intersect_x < coord_min) || (intersect_x > coord_max) {
-        //println!("x out of bounds");
-        return false;
-    }
-    if (intersect_y < coord_min) || (intersect_y > coord_max) {
-        //println!("y out of bounds");
-        return false;
-    }
-    let x_diff1 = intersect_x - (stone1.pos.x as f64);
-    if (x_diff1 > 0.0) != (stone1.vel.x > 0) {
-        //println!("intersection x is in the past for stone 1");
-        return false;
-    }
-    let x_diff2 = intersect_x - (stone2.pos.x as f64);
-    if (x_diff2 > 0.0) != (stone2.vel.x > 0) {
-        //println!("intersection x is in the past for stone 2");
-        return false;
-    }
-    let y_diff1 = intersect_y - (stone1.pos.y as f64);
-    if (y_diff1 > 0.0) != (stone1.vel.y > 0) {
-        //println!("intersection y is in the past for stone 1");
-        return false;
-    }
-    let y_diff2 = intersect_y - (stone2.pos.y as f64);
-    if (y_diff2 > 0.0) != (stone2.vel.y > 0) {
-        //println!("intersection y is in the past for stone 2");
-        return false;
-    }
-    //println!("intersect in bounds");
-    return true;
+    pos1 + vel1 * t1 = pos2 + vel2 * t2
+    crossing both sides with vel2 (or vel1) removes t2 (or t1):
+    t1 = (pos2 - pos1) x vel2 / (vel1 x vel2)
+    t2 = (pos2 - pos1) x vel1 / (vel1 x vel2)
+    */
+    let (v1x, v1y) = (stone1.vel.x as i128, stone1.vel.y as i128);
+    let (v2x, v2y) = (stone2.vel.x as i128, stone2.vel.y as i128);
+    let dx = (stone2.pos.x - stone1.pos.x) as i128;
+    let dy = (stone2.pos.y - stone1.pos.y) as i128;
+    let denominator = cross(v1x, v1y, v2x, v2y);
+    if denominator != 0 {
+        return PathCrossing::Point{
+            t1: Ratio::new(cross(dx, dy, v2x, v2y), denominator),
+            t2: Ratio::new(cross(dx, dy, v1x, v1y), denominator),
+        };
+    }
+    let is_on_line = match ((v1x, v1y), (v2x, v2y)) {
+        ((0, 0), (0, 0)) => (dx, dy) == (0, 0),
+        ((0, 0), _) => cross(dx, dy, v2x, v2y) == 0,
+        _ => cross(dx, dy, v1x, v1y) == 0,
+    };
+    if is_on_line { PathCrossing::Coincident } else { PathCrossing::Parallel }
+}
+
+// range of times t for which start + step * t stays within min..=max, None when empty
+fn get_time_range(start: i128, step: i128, coord_min: i128, coord_max: i128) -> Option<(Option<Ratio>, Option<Ratio>)> {
+    if step == 0 {
+        return ((coord_min..=coord_max).contains(&start)).then_some((None, None));
+    }
+    let to_min = Ratio::new(coord_min - start, step);
+    let to_max = Ratio::new(coord_max - start, step);
+    Some((Some(to_min.min(to_max)), Some(to_min.max(to_max))))
+}
+
+fn is_intersect_within(stone1: &Stone, stone2: &Stone, coord_min: i128, coord_max: i128) -> bool {
+    let (stone1, stone2) = match get_path_crossing(stone1, stone2) {
+        PathCrossing::Parallel => return false,
+        PathCrossing::Point{t1, t2} => {
+            if t1.is_negative() || t2.is_negative() {
+                return false;
+            }
+            let x = t1.along(stone1.pos.x as i128, stone1.vel.x as i128);
+            let y = t1.along(stone1.pos.y as i128, stone1.vel.y as i128);
+            let bounds = Ratio::from_int(coord_min)..=Ratio::from_int(coord_max);
+            return bounds.contains(&x) && bounds.contains(&y);
+        },
+        // measure the shared line in the time of a moving stone, if there is one
+        PathCrossing::Coincident => if (stone1.vel.x, stone1.vel.y) == (0, 0) {
+            (stone2, stone1)
+        } else {
+            (stone1, stone2)
+        },
+    };
+    let (v1x, v1y) = (stone1.vel.x as i128, stone1.vel.y as i128);
+    let (v2x, v2y) = (stone2.vel.x as i128, stone2.vel.y as i128);
+    let dx = (stone2.pos.x - stone1.pos.x) as i128;
+    let dy = (stone2.pos.y - stone1.pos.y) as i128;
+    let speed = v1x * v1x + v1y * v1y;
+    // future of stone 1 is t >= 0, future of stone 2 starts where it is now on stone 1 time scale
+    let mut ranges = vec![(Some(Ratio::from_int(0)), None)];
+    if speed != 0 {
+        let stone2_start = Ratio::new(dx * v1x + dy * v1y, speed);
+        ranges.push(match (v2x * v1x + v2y * v1y).signum() {
+            1 => (Some(stone2_start), None),
+            -1 => (None, Some(stone2_start)),
+            _ => (Some(stone2_start), Some(stone2_start)),
+        });
+    }
+    for (start, step) in [(stone1.pos.x as i128, v1x), (stone1.pos.y as i128, v1y)] {
+        match get_time_range(start, step, coord_min, coord_max) {
+            Some(range) => ranges.push(range),
+            None => return false,
+        }
+    }
+    let low = ranges.iter().filter_map(|x| x.0).max();
+    let high = ranges.iter().filter_map(|x| x.1).min();
+    match (low, high) {
+        (Some(low), Some(high)) => low <= high,
+        _ => true,
+    }
+}
+
+#[test]
+fn test_get_path_crossing() {
+    let stone1 = parse_stone("19, 13, 30 @ -2, 1, -2");
+    let stone2 = parse_stone("18, 19, 22 @ -1, -1, -2");
+    // crossing at x=14.333, y=15.333
+    assert_eq!(PathCrossing::Point{t1: Ratio::new(7, 3), t2: Ratio::new(11, 3)}, get_path_crossing(&stone1, &stone2));
+
+    let stone2 = parse_stone("18, 19, 22 @ -1, -1, -2");
+    let stone3 = parse_stone("20, 25, 34 @ -2, -2, -4");
+    assert_eq!(PathCrossing::Parallel, get_path_crossing(&stone2, &stone3));
+
+    let vertical = parse_stone("5, 0, 0 @ 0, 3, 0");
+    let horizontal = parse_stone("0, 6, 0 @ 2, 0, 0");
+    assert_eq!(PathCrossing::Point{t1: Ratio::new(2, 1), t2: Ratio::new(5, 2)}, get_path_crossing(&vertical, &horizontal));
+    let vertical2 = parse_stone("5, 10, 0 @ 0, -1, 0");
+    assert_eq!(PathCrossing::Coincident, get_path_crossing(&vertical, &vertical2));
+    let standing = parse_stone("5, 7, 0 @ 0, 0, 0");
+    assert_eq!(PathCrossing::Coincident, get_path_crossing(&standing, &vertical));
+    assert_eq!(PathCrossing::Parallel, get_path_crossing(&standing, &horizontal));
+}
+
+#[test]
+fn test_is_intersect_within() {
+    let is_within = |line1: &str, line2: &str| is_intersect_within(&parse_stone(line1), &parse_stone(line2), 0, 10);
+    // crossing in the future, in the past, and outside of the area
+    assert!(is_within("5, 0, 0 @ 0, 3, 0", "0, 6, 0 @ 2, 0, 0"));
+    assert!(!is_within("5, 0, 0 @ 0, -3, 0", "0, 6, 0 @ 2, 0, 0"));
+    assert!(!is_within("5, 0, 0 @ 0, 3, 0", "0, 20, 0 @ 2, 0, 0"));
+    // the same line, moving towards each other, apart and one following the other out of the area
+    assert!(is_within("5, 0, 0 @ 0, 1, 0", "5, 8, 0 @ 0, -1, 0"));
+    assert!(!is_within("5, 2, 0 @ 0, -1, 0", "5, 8, 0 @ 0, 1, 0"));
+    assert!(is_within("5, 2, 0 @ 0, 1, 0", "5, 8, 0 @ 0, 1, 0"));
+    assert!(!is_within("5, 12, 0 @ 0, 1, 0", "5, 15, 0 @ 0, 1, 0"));
+    assert!(is_within("5, 5, 0 @ 0, 0, 0", "5, 8, 0 @ 0, -1, 0"));
+    assert!(!is_within("5, 5, 0 @ 0, 0, 0", "5, 8, 0 @ 0, 1, 0"));
+    assert!(is_within("-5, 5, 0 @ 1, 1, 0", "20, 30, 0 @ -2, -2, 0"));
+}
+
+// time at which both stones are at the same point in space, None if they never meet
+fn get_collision_time(stone1: &Stone, stone2: &Stone) -> Option<Ratio> {
+    // pos1 + vel1 * t = pos2 + vel2 * t, so (pos1 - pos2) = (vel2 - vel1) * t on every axis
+    let axes = [
+        (stone1.pos.x - stone2.pos.x, stone2.vel.x - stone1.vel.x),
+        (stone1.pos.y - stone2.pos.y, stone2.vel.y - stone1.vel.y),
+        (stone1.pos.z - stone2.pos.z, stone2.vel.z - stone1.vel.z),
+    ];
+    let mut time = None;
+    for (distance, closing_speed) in axes {
+        if closing_speed == 0 {
+            if distance != 0 {
+                return None;
+            }
+            continue;
+        }
+        let axis_time = Ratio::new(distance as i128, closing_speed as i128);
+        if time.is_some_and(|x| x != axis_time) {
+            return None;
+        }
+        time = Some(axis_time);
+    }
+    // stones moving together collide all the time, from now on
+    let time = time.unwrap_or(Ratio::from_int(0));
+    (!time.is_negative()).then_some(time)
+}
+
+#[test]
+fn test_get_collision_time() {
+    let rock = parse_stone("24, 13, 10 @ -3, 1, 2");
+    let hailstone = parse_stone("19, 13, 30 @ -2, 1, -2");
+    assert_eq!(Some(Ratio::from_int(5)), get_collision_time(&rock, &hailstone));
+    // same x/y path crossing, but at different times
+    let stone1 = parse_stone("19, 13, 30 @ -2, 1, -2");
+    let stone2 = parse_stone("18, 19, 22 @ -1, -1, -2");
+    assert_eq!(None, get_collision_time(&stone1, &stone2));
+    // collision in the past
+    let stone2 = parse_stone("17, 14, 28 @ -1, 1, 0");
+    assert_eq!(None, get_collision_time(&parse_stone("19, 13, 30 @ -2, 1, -2"), &stone2));
+    assert_eq!(Some(Ratio::new(1, 2)), get_collision_time(&parse_stone("0, 0, 0 @ 2, 2, 2"), &parse_stone("1, 1, 1 @ 0, 0, 0")));
+    assert_eq!(Some(Ratio::from_int(0)), get_collision_time(&stone1, &stone1));
+}
+
+#[test]
+fn test_ratio_cmp() {
+    let values = [-7, -3, -1, 0, 1, 2, 3, 5, 8];
+    for num1 in values {
+        for den1 in [1, 2, 3, 7] {
+            for num2 in values {
+                for den2 in [1, 2, 5, 6] {
+                    let expected = (num1 * den2).cmp(&(num2 * den1));
+                    assert_eq!(expected, Ratio::new(num1, den1).cmp(&Ratio::new(num2, den2)));
+                }
+            }
+        }
+    }
+    let big = i128::MAX / 3;
+    assert!(Ratio::new(big, 1000001) < Ratio::new(big, 1000000));
+    assert!(Ratio::new(-big, 1000001) > Ratio::new(-big, 1000000));
+}
+
+struct Hailstorm {
+    stones: Vec<Stone>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Approach {
+    stone1: usize,
+    stone2: usize,
+    time: Ratio,
+    distance_squared: Ratio,
+}
+
+impl Approach {
+    fn distance(&self) -> f64 {
+        self.distance_squared.to_f64().sqrt()
+    }
+}
+
+impl Hailstorm {
+    fn parse(input: &str) -> Self {
+        Self{stones: input.trim().lines().map(parse_stone).collect()}
+    }
+
+    #[allow(dead_code)]
+    fn positions_at(&self, time: isize) -> Vec<Position> {
+        self.stones.iter().map(|x| x.position_at(time)).collect()
+    }
+
+    // closest approach of every pair of stones, from now on
+    fn get_closest_approaches(&self) -> Vec<Approach> {
+        let mut approaches = vec![];
+        for (index1, stone1) in self.stones.iter().enumerate() {
+            for (index2, stone2) in self.stones.iter().enumerate().skip(index1 + 1) {
+                let (time, distance_squared) = get_closest_approach(stone1, stone2);
+                approaches.push(Approach{stone1: index1, stone2: index2, time, distance_squared});
+            }
+        }
+        approaches
+    }
+
+    // collision times of the rock with every hailstone, or the first hailstone it misses
+    fn get_rock_collisions(&self, rock: &Stone) -> Result<Vec<Ratio>, usize> {
+        self.stones
+            .iter()
+            .enumerate()
+            .map(|(index, stone)| get_collision_time(rock, stone).ok_or(index))
+            .collect()
+    }
+}
+
+fn get_closest_approach(stone1: &Stone, stone2: &Stone) -> (Ratio, Ratio) {
+    /*
+    the distance vector is d + w * t, with d = pos2 - pos1 and w = vel2 - vel1
+    its square is smallest at t = -(d . w) / (w . w), where it equals |d x w|^2 / (w . w)
+    if that time is in the past, the stones are the closest now
+    */
+    let d = [
+        (stone2.pos.x - stone1.pos.x) as i128,
+        (stone2.pos.y - stone1.pos.y) as i128,
+        (stone2.pos.z - stone1.pos.z) as i128,
+    ];
+    let w = [
+        (stone2.vel.x - stone1.vel.x) as i128,
+        (stone2.vel.y - stone1.vel.y) as i128,
+        (stone2.vel.z - stone1.vel.z) as i128,
+    ];
+    let dot = |a: [i128; 3], b: [i128; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let relative_speed = dot(w, w);
+    let approach_speed = dot(d, w);
+    if (relative_speed == 0) || (approach_speed >= 0) {
+        return (Ratio::from_int(0), Ratio::from_int(dot(d, d)));
+    }
+    let cross = [
+        d[1] * w[2] - d[2] * w[1],
+        d[2] * w[0] - d[0] * w[2],
+        d[0] * w[1] - d[1] * w[0],
+    ];
+    (Ratio::new(-approach_speed, relative_speed), Ratio::new(dot(cross, cross), relative_speed))
+}
+
+#[test]
+fn test_hailstorm() {
+    let input = "
+19, 13, 30 @ -2,  1, -2
+18, 19, 22 @ -1, -1, -2
+20, 25, 34 @ -2, -2, -4
+12, 31, 28 @ -1, -2, -1
+20, 19, 15 @  1, -5, -3
+";
+    let hailstorm = Hailstorm::parse(input);
+    assert_eq!(Position{x: 17, y: 14, z: 28}, hailstorm.positions_at(1)[0]);
+    assert_eq!(Position{x: 20, y: 19, z: 15}, hailstorm.positions_at(0)[4]);
+
+    let rock = parse_stone("24, 13, 10 @ -3, 1, 2");
+    let times = [5, 3, 4, 6, 1].map(Ratio::from_int).to_vec();
+    assert_eq!(Ok(times), hailstorm.get_rock_collisions(&rock));
+    let rock = parse_stone("24, 13, 10 @ -3, 1, 1");
+    assert_eq!(Err(0), hailstorm.get_rock_collisions(&rock));
+
+    let approaches = hailstorm.get_closest_approaches();
+    assert_eq!(10, approaches.len());
+    for approach in approaches.iter() {
+        // the closest approach is no further than the distance at any whole time around it
+        let stone1 = &hailstorm.stones[approach.stone1];
+        let stone2 = &hailstorm.stones[approach.stone2];
+        for time in 0..20 {
+            let (a, b) = (stone1.position_at(time), stone2.position_at(time));
+            let distance_squared = (a.x - b.x).pow(2) + (a.y - b.y).pow(2) + (a.z - b.z).pow(2);
+            assert!(approach.distance_squared <= Ratio::from_int(distance_squared as i128));
+        }
+    }
+    assert_eq!(
+        Approach{stone1: 1, stone2: 2, time: Ratio::new(16, 3), distance_squared: Ratio::new(40, 3)},
+        approaches[4],
+    );
+    // the stones pass each other along x at 4/3, still 3 apart along y
+    let stone1 = parse_stone("0, 0, 0 @ 1, 0, 0");
+    let stone2 = parse_stone("4, 3, 0 @ -2, 0, 0");
+    assert_eq!((Ratio::new(4, 3), Ratio::from_int(9)), get_closest_approach(&stone1, &stone2));
 }
 
 fn process_input_pt2(input: &str) -> usize {
-    let stones = input.trim().lines().map(parse_stone).collect::<Vec<_>>();
+    let _stones = input.trim().lines().map(parse_stone).collect::<Vec<_>>();
     /*
     todo: need to intersect all lines
         at different times in the future
@@ -0,0 +1,264 @@
+use std::cmp;
+use std::ops::{Add, Range, Sub};
+#[cfg(test)]
+use crate::random::XorShift;
+
+pub trait Bound: Copy + Ord + Default + Add<Output = Self> + Sub<Output = Self> {}
+
+impl<T: Copy + Ord + Default + Add<Output = T> + Sub<Output = T>> Bound for T {}
+
+// ranges are half-open, sorted, non-empty, and neither overlap nor touch each other
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self{ranges: vec![]}
+    }
+
+    pub fn from_ranges(ranges: impl IntoIterator<Item = Range<T>>) -> Self {
+        let mut ranges = ranges
+            .into_iter()
+            .filter(|range| range.start < range.end)
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|x| x.start);
+        let mut coalesced: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match coalesced.last_mut() {
+                Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+                _ => coalesced.push(range),
+            }
+        }
+        Self{ranges: coalesced}
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn len(&self) -> T {
+        self.ranges.iter().fold(T::default(), |sum, range| sum + (range.end - range.start))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    // exclusive, like the end of the last range
+    pub fn end(&self) -> Option<T> {
+        self.ranges.last().map(|range| range.end)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.ranges.iter().any(|range| range.contains(&value))
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        let ranges = std::mem::take(&mut self.ranges);
+        *self = Self::from_ranges(ranges.into_iter().chain([range]));
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_ranges(self.ranges.iter().chain(other.ranges.iter()).cloned())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+        while (i < self.ranges.len()) && (j < other.ranges.len()) {
+            let first = &self.ranges[i];
+            let second = &other.ranges[j];
+            let start = cmp::max(first.start, second.start);
+            let end = cmp::min(first.end, second.end);
+            if start < end {
+                result.push(start..end);
+            }
+            if first.end < second.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self{ranges: result}
+    }
+
+    // same as a non-empty intersection, without building it
+    pub fn intersects(&self, other: &Self) -> bool {
+        let (mut i, mut j) = (0, 0);
+        while (i < self.ranges.len()) && (j < other.ranges.len()) {
+            let first = &self.ranges[i];
+            let second = &other.ranges[j];
+            if (first.start < second.end) && (second.start < first.end) {
+                return true;
+            }
+            if first.end < second.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        false
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = vec![];
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            while (j < other.ranges.len()) && (other.ranges[j].end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while (k < other.ranges.len()) && (other.ranges[k].start < range.end) {
+                let cut = &other.ranges[k];
+                if cut.start > start {
+                    result.push(start..cut.start);
+                }
+                start = cmp::max(start, cut.end);
+                k += 1;
+            }
+            if start < range.end {
+                result.push(start..range.end);
+            }
+        }
+        Self{ranges: result}
+    }
+
+    // returns the values below `at` and the values starting from `at`
+    pub fn split_at(&self, at: T) -> (Self, Self) {
+        let mut below = vec![];
+        let mut above = vec![];
+        for range in &self.ranges {
+            if range.end <= at {
+                below.push(range.clone());
+            } else if range.start >= at {
+                above.push(range.clone());
+            } else {
+                below.push(range.start..at);
+                above.push(at..range.end);
+            }
+        }
+        (Self{ranges: below}, Self{ranges: above})
+    }
+
+    pub fn shift(&self, delta: T) -> Self {
+        let ranges = self.ranges
+            .iter()
+            .map(|range| (range.start + delta)..(range.end + delta))
+            .collect();
+        Self{ranges}
+    }
+}
+
+impl<T: Bound> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        Self::from_ranges([range])
+    }
+}
+
+#[test]
+fn test_from_ranges() {
+    let set = IntervalSet::from_ranges([5..7, 1..3, 3..4, 10..10, 6..9]);
+    assert_eq!([1..4, 5..9], set.ranges());
+    assert_eq!(7, set.len());
+    assert_eq!(Some(1), set.min());
+    assert_eq!(Some(9), set.end());
+    assert!(!set.contains(9));
+    assert!(set.contains(3));
+    assert!(!set.contains(4));
+    assert!(IntervalSet::<usize>::new().is_empty());
+
+    let mut set = IntervalSet::from(1..3);
+    set.insert(-2..0);
+    set.insert(0..1);
+    assert_eq!(IntervalSet::from(-2..3), set);
+}
+
+#[test]
+fn test_set_operations() {
+    let first = IntervalSet::from_ranges([0..5, 10..15]);
+    let second = IntervalSet::from_ranges([3..12, 14..20]);
+    assert_eq!(IntervalSet::from(0..20), first.union(&second));
+    assert_eq!([3..5, 10..12, 14..15], first.intersection(&second).ranges());
+    assert!(first.intersects(&second));
+    assert!(!first.intersects(&IntervalSet::from_ranges([5..10, 15..20])));
+    assert_eq!([0..3, 12..14], first.difference(&second).ranges());
+    assert_eq!([5..10, 15..20], second.difference(&first).ranges());
+
+    let (below, above) = first.split_at(12);
+    assert_eq!([0..5, 10..12], below.ranges());
+    assert_eq!(IntervalSet::from(12..15), above);
+    let (below, above) = first.split_at(5);
+    assert_eq!(IntervalSet::from(0..5), below);
+    assert_eq!(IntervalSet::from(10..15), above);
+
+    assert_eq!([-3..2, 7..12], first.shift(-3).ranges());
+}
+
+#[test]
+fn test_properties_against_brute_force() {
+    const SIZE: i32 = 40;
+    let mut random = XorShift::new(0x2545f4914f6cdd1d);
+    let mut next_random = |limit: i32| random.below(limit as usize) as i32;
+    let random_set = |next_random: &mut dyn FnMut(i32) -> i32| {
+        let count = next_random(5);
+        let ranges = (0..count)
+            .map(|_| {
+                let start = next_random(SIZE);
+                start..(start + next_random(10)).min(SIZE)
+            })
+            .collect::<Vec<_>>();
+        IntervalSet::from_ranges(ranges)
+    };
+    let to_values = |set: &IntervalSet<i32>| (-SIZE..2 * SIZE).filter(|&x| set.contains(x)).collect::<Vec<_>>();
+    let assert_normalized = |set: &IntervalSet<i32>| {
+        assert!(set.ranges().iter().all(|range| range.start < range.end));
+        assert!(set.ranges().windows(2).all(|pair| pair[0].end < pair[1].start));
+    };
+
+    for _ in 0..500 {
+        let first = random_set(&mut next_random);
+        let second = random_set(&mut next_random);
+        let first_values = to_values(&first);
+        let second_values = to_values(&second);
+
+        let union = first.union(&second);
+        assert_normalized(&union);
+        let mut expected = first_values.iter().chain(second_values.iter()).copied().collect::<Vec<_>>();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(expected, to_values(&union));
+
+        let intersection = first.intersection(&second);
+        assert_normalized(&intersection);
+        let expected = first_values.iter().filter(|x| second_values.contains(x)).copied().collect::<Vec<_>>();
+        assert_eq!(expected, to_values(&intersection));
+        assert_eq!(!intersection.is_empty(), first.intersects(&second));
+
+        let difference = first.difference(&second);
+        assert_normalized(&difference);
+        let expected = first_values.iter().filter(|x| !second_values.contains(x)).copied().collect::<Vec<_>>();
+        assert_eq!(expected, to_values(&difference));
+        assert_eq!(first.len(), difference.len() + intersection.len());
+
+        let at = next_random(SIZE);
+        let (below, above) = first.split_at(at);
+        assert_normalized(&below);
+        assert_normalized(&above);
+        assert!(to_values(&below).iter().all(|&x| x < at));
+        assert!(to_values(&above).iter().all(|&x| x >= at));
+        assert_eq!(first, below.union(&above));
+
+        let delta = next_random(2 * SIZE) - SIZE;
+        let shifted = first.shift(delta);
+        assert_normalized(&shifted);
+        assert_eq!(first.len(), shifted.len());
+        assert_eq!(first, shifted.shift(-delta));
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+pub type Pos = (isize, isize);
+pub type NodeId = usize;
+pub type EdgeId = usize;
+
+// a corridor walked from one junction to the next, `path` lists its tiles after `from` up to `to`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub steps: usize,
+    pub path: Vec<Pos>,
+}
+
+/*
+Grid maze with its corridors contracted: every open tile that does not have exactly two open
+neighbours (junctions, dead ends, entrances on the border) becomes a node, and every corridor
+between two nodes that can be walked in one direction becomes an edge in that direction.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct JunctionGraph {
+    nodes: Vec<Pos>,
+    node_ids: HashMap<Pos, NodeId>,
+    edges: Vec<Edge>,
+    outgoing: Vec<Vec<EdgeId>>,
+}
+
+const NEIGHBOUR_OFFSETS: [Pos; 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const CLOCKWISE_OFFSETS: [Pos; 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+fn get_clockwise_index(from: Pos, to: Pos) -> usize {
+    let offset = (to.0 - from.0, to.1 - from.1);
+    CLOCKWISE_OFFSETS.iter().position(|&x| x == offset).unwrap()
+}
+
+impl JunctionGraph {
+    // `is_open` tells which tiles can be walked on at all, `can_step` whether a single step
+    // between two neighbouring open tiles is allowed in that direction
+    pub fn build(
+        rows_count: usize,
+        cols_count: usize,
+        is_open: impl Fn(Pos) -> bool,
+        can_step: impl Fn(Pos, Pos) -> bool,
+    ) -> Self {
+        let is_open = |pos: Pos| {
+            (pos.0 >= 0) && (pos.1 >= 0) && (pos.0 < rows_count as isize) && (pos.1 < cols_count as isize) && is_open(pos)
+        };
+        let get_open_neighbours = |pos: Pos| {
+            NEIGHBOUR_OFFSETS
+                .iter()
+                .map(|offset| (pos.0 + offset.0, pos.1 + offset.1))
+                .filter(|&x| is_open(x))
+                .collect::<Vec<_>>()
+        };
+
+        let mut graph = Self::default();
+        for row in 0..rows_count as isize {
+            for col in 0..cols_count as isize {
+                if is_open((row, col)) && (get_open_neighbours((row, col)).len() != 2) {
+                    graph.node_ids.insert((row, col), graph.nodes.len());
+                    graph.nodes.push((row, col));
+                    graph.outgoing.push(vec![]);
+                }
+            }
+        }
+
+        for from in 0..graph.nodes.len() {
+            let from_pos = graph.nodes[from];
+            'corridors: for first_pos in get_open_neighbours(from_pos) {
+                let mut prev_pos = from_pos;
+                let mut current_pos = first_pos;
+                let mut path = vec![];
+                loop {
+                    if !can_step(prev_pos, current_pos) {
+                        continue 'corridors;
+                    }
+                    path.push(current_pos);
+                    if let Some(&to) = graph.node_ids.get(&current_pos) {
+                        graph.add_edge(Edge{from, to, steps: path.len(), path});
+                        break;
+                    }
+                    // not a node, so there is exactly one way on
+                    let next_pos = get_open_neighbours(current_pos)
+                        .into_iter()
+                        .find(|&x| x != prev_pos)
+                        .unwrap();
+                    prev_pos = current_pos;
+                    current_pos = next_pos;
+                }
+            }
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, edge: Edge) {
+        self.outgoing[edge.from].push(self.edges.len());
+        self.edges.push(edge);
+    }
+
+    pub fn nodes_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node_id(&self, pos: Pos) -> Option<NodeId> {
+        self.node_ids.get(&pos).copied()
+    }
+
+    pub fn node_pos(&self, node: NodeId) -> Pos {
+        self.nodes[node]
+    }
+
+    pub fn edge(&self, edge: EdgeId) -> &Edge {
+        &self.edges[edge]
+    }
+
+    pub fn outgoing(&self, node: NodeId) -> impl Iterator<Item = &Edge> + '_ {
+        self.outgoing[node].iter().map(|&x| &self.edges[x])
+    }
+
+    pub fn outgoing_ids(&self, node: NodeId) -> &[EdgeId] {
+        &self.outgoing[node]
+    }
+
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.outgoing(from).any(|x| x.to == to)
+    }
+
+    /*
+    Nodes met walking around the face that the only edge of `start` sticks into, always taking
+    the next corridor clockwise from the one we came by, until back at `start`. Corridors never
+    cross, so the grid directions they leave the nodes in give the planar embedding, and when
+    `start` is on the border of the grid this is the outer face. Expects the edges to go both
+    ways, None if `start` has more than one edge or the walk does not close.
+    */
+    pub fn walk_face(&self, start: NodeId) -> Option<Vec<NodeId>> {
+        let &[mut edge] = &self.outgoing[start][..] else {
+            return None;
+        };
+        let mut walk = vec![start];
+        // every side of every edge is walked at most once
+        for _ in 0..(2 * self.edges.len()) {
+            let Edge{from, to, path, ..} = &self.edges[edge];
+            walk.push(*to);
+            if *to == start {
+                return Some(walk);
+            }
+            let to_pos = self.nodes[*to];
+            let prev_pos = if path.len() >= 2 { path[path.len() - 2] } else { self.nodes[*from] };
+            let came_by = get_clockwise_index(to_pos, prev_pos);
+            edge = (1..=4).find_map(
+                |turn|
+                    self.outgoing[*to]
+                        .iter()
+                        .copied()
+                        .find(|&x| get_clockwise_index(to_pos, self.edges[x].path[0]) == (came_by + turn) % 4)
+            )?;
+        }
+        None
+    }
+
+    // whether every corridor can be walked both ways
+    pub fn is_undirected(&self) -> bool {
+        self.outgoing.iter().flatten().all(
+            |&x| {
+                let Edge{from, to, path, ..} = &self.edges[x];
+                let back_first_pos = if path.len() >= 2 { path[path.len() - 2] } else { self.nodes[*from] };
+                self.outgoing(*to).any(|back| (back.to == *from) && (back.path[0] == back_first_pos))
+            }
+        )
+    }
+
+    // edge ids stay valid, the edge is only detached from its node
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId) {
+        let edges = &self.edges;
+        self.outgoing[from].retain(|&x| edges[x].to != to);
+    }
+}
+
+#[cfg(test)]
+fn parse_maze(input: &str) -> Vec<Vec<char>> {
+    input.trim().lines().map(|line| line.chars().collect()).collect()
+}
+
+#[test]
+fn test_build() {
+    let maze = parse_maze("
+#.#####
+#...#.#
+#.#...#
+#.###.#
+#.....#
+#####.#
+");
+    let graph = JunctionGraph::build(
+        maze.len(),
+        maze[0].len(),
+        |(row, col)| maze[row as usize][col as usize] != '#',
+        |_, _| true,
+    );
+    let mut nodes = (0..graph.nodes_count()).map(|x| graph.node_pos(x)).collect::<Vec<_>>();
+    nodes.sort();
+    // entrance, a junction, a dead end, two more junctions and the exit
+    assert_eq!(vec![(0, 1), (1, 1), (1, 5), (2, 5), (4, 5), (5, 5)], nodes);
+
+    let id = |pos| graph.node_id(pos).unwrap();
+    let mut edges = graph
+        .outgoing(id((1, 1)))
+        .map(|x| (graph.node_pos(x.to), x.steps))
+        .collect::<Vec<_>>();
+    edges.sort();
+    assert_eq!(vec![((0, 1), 1), ((2, 5), 5), ((4, 5), 7)], edges);
+    let edge = graph.outgoing(id((1, 1))).find(|x| x.to == id((2, 5))).unwrap();
+    assert_eq!(vec![(1, 2), (1, 3), (2, 3), (2, 4), (2, 5)], edge.path);
+    assert!(graph.has_edge(id((1, 5)), id((2, 5))));
+    assert_eq!(None, graph.node_id((1, 2)));
+    assert!(graph.is_undirected());
+}
+
+#[test]
+fn test_walk_face() {
+    let maze = parse_maze("
+#.#####
+#...#.#
+#.#...#
+#.###.#
+#.....#
+#####.#
+");
+    let graph = JunctionGraph::build(
+        maze.len(),
+        maze[0].len(),
+        |(row, col)| maze[row as usize][col as usize] != '#',
+        |_, _| true,
+    );
+    let walk = graph
+        .walk_face(graph.node_id((0, 1)).unwrap())
+        .unwrap()
+        .iter()
+        .map(|&x| graph.node_pos(x))
+        .collect::<Vec<_>>();
+    // around the loop on the right side, into the dead end and down to the exit, back around the left side
+    let expected = vec![(0, 1), (1, 1), (2, 5), (1, 5), (2, 5), (4, 5), (5, 5), (4, 5), (1, 1), (0, 1)];
+    assert_eq!(expected, walk);
+    assert_eq!(None, graph.walk_face(graph.node_id((1, 1)).unwrap()));
+}
+
+#[test]
+fn test_build_one_way() {
+    let maze = parse_maze("
+#.###
+#.>.#
+#.#.#
+#...#
+###.#
+");
+    let graph = JunctionGraph::build(
+        maze.len(),
+        maze[0].len(),
+        |(row, col)| maze[row as usize][col as usize] != '#',
+        |from, to| (maze[to.0 as usize][to.1 as usize] != '>') || (to.1 > from.1),
+    );
+    let id = |pos| graph.node_id(pos).unwrap();
+    assert!(graph.has_edge(id((1, 1)), id((3, 3))));
+    assert!(graph.has_edge(id((3, 3)), id((1, 1))));
+    // the corridor over the slope only goes one way, the one through the bottom goes both
+    assert_eq!(2, graph.outgoing(id((1, 1))).filter(|x| x.to == id((3, 3))).count());
+    assert_eq!(1, graph.outgoing(id((3, 3))).filter(|x| x.to == id((1, 1))).count());
+    assert!(!graph.is_undirected());
+
+    let mut graph = graph.clone();
+    graph.remove_edge(id((3, 3)), id((1, 1)));
+    assert!(!graph.has_edge(id((3, 3)), id((1, 1))));
+    assert_eq!(3, graph.outgoing_ids(id((1, 1))).len());
+}
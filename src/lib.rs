@@ -0,0 +1,3 @@
+pub mod interval_set;
+pub mod junction_graph;
+pub mod random;
@@ -0,0 +1,33 @@
+// xorshift64, repeatable pseudo random numbers for generating test inputs, the seed must not be 0
+#[derive(Clone, Debug)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        Self{state: seed}
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // a number in 0..limit
+    pub fn below(&mut self, limit: usize) -> usize {
+        (self.next_u64() % limit as u64) as usize
+    }
+}
+
+#[test]
+fn test_xor_shift() {
+    let mut random = XorShift::new(1);
+    assert_eq!(1082269761, random.next_u64());
+    let mut first = XorShift::new(0x2545f4914f6cdd1d);
+    let mut second = first.clone();
+    assert_eq!(first.next_u64() % 7, second.below(7) as u64);
+    assert!((0..100).all(|_| first.below(3) < 3));
+}